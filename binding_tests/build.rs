@@ -0,0 +1,131 @@
+use gl_generator::{Api, Fallbacks, Profile, Registry};
+use gl_generator_profiling_struct::ProfilingStructGenerator;
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn out_path(name: &str) -> PathBuf {
+    Path::new(&env::var("OUT_DIR").unwrap()).join(format!("{}.rs", name))
+}
+
+fn emit(registry: &Registry, name: &str, generator: ProfilingStructGenerator) {
+    let mut file = File::create(out_path(name)).unwrap();
+    registry.write_bindings(generator, &mut file).unwrap();
+}
+
+fn main() {
+    let gl = Registry::new(Api::Gl, (4, 5), Profile::Core, Fallbacks::All, ["GL_NV_command_list"]);
+    let gl31 = Registry::new(Api::Gl, (3, 1), Profile::Core, Fallbacks::None, []);
+    let gles = Registry::new(Api::Gles2, (3, 2), Profile::Core, Fallbacks::All, []);
+    let egl = Registry::new(Api::Egl, (1, 5), Profile::Core, Fallbacks::All, []);
+    let glx = Registry::new(Api::Glx, (1, 4), Profile::Core, Fallbacks::All, []);
+
+    emit(&gl, "gl_default", ProfilingStructGenerator);
+    emit(&gl, "gl_counts", ProfilingStructGenerator::counts_only());
+    emit(&gl, "gl_full", ProfilingStructGenerator::full_debug()
+        .with_debug_messages(true)
+        .with_command_toggles(true)
+        .with_gpu_debug_groups(true)
+        .with_object_labels(true)
+        .with_gl_info(true)
+        .with_dry_run(true)
+        .with_usability_probe(true)
+        .with_compact_fnptr(true)
+        .with_self_benchmark(true)
+        .with_abort_on_oom(true)
+        .with_named_trampolines(true)
+        .with_enum_names(true)
+        .with_return_checks(true)
+        .with_instance_counters(true)
+        .with_must_use(true));
+    emit(&gl, "gl_custom", ProfilingStructGenerator::new()
+        .with_cpu_timing(true)
+        .with_cpu_timing_filter(["glDraw*"])
+        .with_error_buffer_size(16)
+        .with_trace_capacity(32)
+        .with_frame_history(2)
+        .with_frame_auto_reset(true)
+        .with_global_instance(true)
+        .with_signal_dump(true)
+        .with_windowed_stats(true)
+        .with_last_args(true)
+        .with_error_context_depth(4)
+        .with_count_hooks(true)
+        .with_wrapper_attribute("#[cfg_attr(coverage_nightly, coverage(off))]")
+        .with_cached_get_error(true)
+        .with_redundant_call_checks(["glClear"])
+        .with_repr_c(true)
+        .with_doc_links(true)
+        .with_resolved_symbols(true)
+        .with_stack_error_buffer(256)
+        .with_gpu_fence_timing(["glDrawArrays"]));
+    emit(&gl, "gl_prefixed", ProfilingStructGenerator::full_debug()
+        .with_profiler_prefix("gl_prof_")
+        .with_gpu_debug_groups(true)
+        .with_gl_info(true)
+        .with_dry_run(true)
+        .with_usability_probe(true)
+        .with_self_benchmark(true)
+        .with_command_toggles(true)
+        .with_debug_messages(true)
+        .with_return_checks(true)
+        .with_category_counts(true)
+        .with_error_buffer_size(16)
+        .with_batched_errors(true)
+        .with_trace_capacity(32)
+        .with_frame_history(2)
+        .with_frame_auto_reset(true)
+        .with_signal_dump(true)
+        .with_windowed_stats(true)
+        .with_last_args(true)
+        .with_count_hooks(true)
+        .with_redundant_call_checks(["glClear"])
+        .with_stack_error_buffer(256)
+        .with_instance_counters(true)
+        .with_gpu_fence_timing(["glDrawArrays"]));
+    emit(&gl, "gl_single", ProfilingStructGenerator::full_debug().with_serde(true).with_single_thread(true).with_mock(true));
+    emit(&gl, "gl_batched", ProfilingStructGenerator::new().with_batched_errors(true).with_sampling(4));
+    emit(&gl, "gl_panic", ProfilingStructGenerator::new().with_panic_on_error(true).with_thread_local_errors(true).with_error_passthrough(true));
+    emit(&gl, "gl_bare", ProfilingStructGenerator::new()
+        .with_profiling_const(false)
+        .with_assert_send(false)
+        .with_bare_struct("BareGl")
+        .with_types(false)
+        .with_types_path("crate::gl_default::types"));
+    emit(&gl31, "gl_compat", ProfilingStructGenerator::new().with_struct_generator_compat(true));
+    emit(&gles, "gles_default", ProfilingStructGenerator::new()
+        .with_profiler_prefix("gles_prof_")
+        .with_api_version((3, 2), Profile::Core)
+        .with_api_in_logs(true)
+        .with_global_original_names(true)
+        .with_command_allowlist(["glClear", "glDrawArrays"]));
+    emit(&egl, "egl_default", ProfilingStructGenerator::new().with_mock(true));
+    emit(&glx, "glx_default", ProfilingStructGenerator::new());
+
+    {
+        let mut file = File::create(out_path("combined")).unwrap();
+        gl.write_bindings(ProfilingStructGenerator::new().with_module("gl"), &mut file).unwrap();
+        gles.write_bindings(ProfilingStructGenerator::counts_only().with_module("gles"), &mut file).unwrap();
+    }
+    {
+        let mut types = File::create(out_path("gl_split_types")).unwrap();
+        let mut bindings = File::create(out_path("gl_split")).unwrap();
+        ProfilingStructGenerator::full_debug().write_split(&gl, &mut types, &mut bindings).unwrap();
+    }
+    {
+        // the trait implemented by the bindings generated `with_impl_trait`
+        let mut file = File::create(out_path("gl_backend")).unwrap();
+        writeln!(file, "#[allow(non_snake_case, clippy::missing_safety_doc)]\npub trait GlBackend {{").unwrap();
+        for cmd in &gl.cmds {
+            let fix = |ty: &str| ty.replace("types::", "crate::gl_default::types::").replace("__gl_imports::raw", "std::os::raw");
+            let params: Vec<_> = cmd.params.iter().map(|p| format!("_: {}", fix(&p.ty))).collect();
+            writeln!(file, "    unsafe fn {}(&self, {}) -> {};", cmd.proto.ident, params.join(", "), fix(&cmd.proto.ty)).unwrap();
+        }
+        writeln!(file, "}}").unwrap();
+    }
+    emit(&gl, "gl_backend_impl", ProfilingStructGenerator::new()
+        .with_impl_trait("crate::GlBackend")
+        .with_types(false)
+        .with_types_path("crate::gl_default::types"));
+}
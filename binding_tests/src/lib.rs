@@ -0,0 +1,113 @@
+//! Includes the bindings generated by `build.rs`, so that building this crate checks that they
+//! compile under edition 2024 without warnings, also where `unsafe_op_in_unsafe_fn` is denied,
+//! and runs the generated helpers which can work without a GL context.
+
+#![deny(warnings, unsafe_op_in_unsafe_fn)]
+
+macro_rules! bindings {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[allow(clippy::all)]
+            pub mod $name {
+                include!(concat!(env!("OUT_DIR"), "/", stringify!($name), ".rs"));
+            }
+        )*
+    };
+}
+
+bindings!(
+    gl_default,
+    gl_counts,
+    gl_full,
+    gl_custom,
+    gl_prefixed,
+    gl_single,
+    gl_batched,
+    gl_panic,
+    gl_bare,
+    gl_compat,
+    gles_default,
+    combined,
+    gl_split_types,
+    gl_backend_impl,
+);
+
+#[allow(clippy::all, non_snake_case)]
+mod gl_backend {
+    include!(concat!(env!("OUT_DIR"), "/gl_backend.rs"));
+}
+pub use gl_backend::GlBackend;
+
+#[allow(clippy::all)]
+pub mod gl_split {
+    pub use super::gl_split_types::*;
+    include!(concat!(env!("OUT_DIR"), "/gl_split.rs"));
+}
+
+#[allow(clippy::all, non_camel_case_types, non_snake_case)]
+pub mod glx_default {
+    include!(concat!(env!("OUT_DIR"), "/glx_default.rs"));
+}
+
+#[allow(clippy::all, non_camel_case_types)]
+pub mod egl_default {
+    // the platform types which the EGL bindings expect in scope
+    pub type khronos_utime_nanoseconds_t = u64;
+    pub type khronos_uint64_t = u64;
+    pub type khronos_ssize_t = isize;
+    pub type EGLNativeDisplayType = *const std::os::raw::c_void;
+    pub type EGLNativePixmapType = *const std::os::raw::c_void;
+    pub type EGLNativeWindowType = *const std::os::raw::c_void;
+    pub type EGLint = i32;
+    pub type NativeDisplayType = *const std::os::raw::c_void;
+    pub type NativePixmapType = *const std::os::raw::c_void;
+    pub type NativeWindowType = *const std::os::raw::c_void;
+    include!(concat!(env!("OUT_DIR"), "/egl_default.rs"));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gl_single as g;
+
+    #[test]
+    fn baseline_round_trip() {
+        let gl = g::Gl::mock();
+        g::profiler_reset();
+        for _ in 0..10 {
+            unsafe { gl.Clear(0) };
+        }
+        let baseline = g::profiler_to_json();
+        assert_eq!(g::profiler_check_against_baseline(&baseline, 0.0), Ok(()));
+
+        // 11 calls against 10 is a growth of 10%
+        unsafe { gl.Clear(0) };
+        assert_eq!(g::profiler_check_against_baseline(&baseline, 0.2), Ok(()));
+        let regressions = g::profiler_check_against_baseline(&baseline, 0.05).unwrap_err();
+        assert_eq!(
+            regressions,
+            vec![
+                "all commands: 10 calls in the baseline, 11 now".to_string(),
+                "glClear: 10 calls in the baseline, 11 now".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn baseline_rejects_other_layouts() {
+        let valid = r#"{"calls":1,"errors":0,"time_ns":0,"commands":[{"name":"glClear","calls":1,"errors":0,"time_ns":0}]}"#;
+        let pretty = valid.replace(',', ",\n  ").replace(':', ": ");
+        assert_eq!(g::profiler_check_against_baseline(&pretty, 1000.0), Ok(()));
+        // the whitespace inside a name is kept, so the name matches no command
+        assert_eq!(g::profiler_check_against_baseline(&valid.replace("glClear", "gl Clear"), 1000.0), Ok(()));
+        for invalid in [
+            "",
+            &valid[..40],
+            &valid.replace(r#""errors":0,"time_ns""#, r#""time_ns":0,"errors""#),
+            &valid.replace(r#""time_ns":0}"#, r#""time_ns":0,"extra":0}"#),
+            &valid.replace("glClear", r"gl\u0043lear"),
+            &format!("{} {{}}", valid),
+        ] {
+            assert!(g::profiler_check_against_baseline(invalid, 1000.0).is_err(), "{}", invalid);
+        }
+    }
+}
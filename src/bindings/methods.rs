@@ -0,0 +1,572 @@
+//! Writers of the optional methods of the struct.
+
+use gl_generator::{Registry, generators};
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+use crate::helpers::errors::gen_error_codes;
+
+/// Creates the `gl_assert_no_error!` macro, which panics if the errors drained with
+/// `poll_errors_into` are not empty.
+///
+/// The macro is private to the module of the bindings, and exported from it with a `use`, so
+/// several bindings in one crate do not clash.
+pub(crate) fn write_assert_macro<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+/// Drains the pending errors of the given bindings, and panics with their descriptions and the
+/// location of the assertion if there were any.
+#[allow(unused_macros)]
+macro_rules! gl_assert_no_error {
+    ($gl:expr) => {{
+        let mut errors = Vec::new();
+        $gl.poll_errors_into(&mut errors);
+        if !errors.is_empty() {
+            let descriptions: Vec<String> = errors
+                .iter()
+                .map(|&(code, description)| format!("0x{:04X} {}", code, description))
+                .collect();
+            panic!(
+                "{}:{}: {} GL error(s): {}",
+                file!(),
+                line!(),
+                errors.len(),
+                descriptions.join("; ")
+            );
+        }
+    }};
+}
+#[allow(unused_imports)]
+pub(crate) use gl_assert_no_error;
+"##
+        .as_bytes(),
+    )
+}
+
+/// Creates the methods which look up a `FnPtr` by the native symbol name of its command.
+///
+/// These are written inside the `impl` created by `write_impl`.
+pub(crate) fn write_lookup_fns<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        "#[allow(dead_code)]
+        fn fn_ptr_by_symbol(&self, symbol: &str) -> Option<&FnPtr> {{
+            match command_index(symbol)? {{"
+    )?;
+
+    for (index, cmd) in registry.cmds.iter().enumerate() {
+        writeln!(
+            dest,
+            "{index} => Some(&self.{name}),",
+            index = index,
+            name = cmd.proto.ident,
+        )?;
+    }
+
+    writeln!(
+        dest,
+        "_ => None,
+            }}
+        }}
+
+        /// Returns `true` if every named function (e.g. `\"glCreateQueries\"`) has been loaded.
+        ///
+        /// This can be used to infer the support of an extension from its entry points when the
+        /// extension string is unavailable. Unknown names are reported as not loaded.
+        #[allow(dead_code)]
+        pub fn all_loaded(&self, names: &[&str]) -> bool {{
+            names.iter().all(|name| self.fn_ptr_by_symbol(name).map_or(false, FnPtr::is_loaded))
+        }}
+
+        /// Returns `true` if the named function, or any other function of its alias group (see
+        /// `alias_group`), has been loaded, e.g. `glDrawArraysInstancedARB` for
+        /// `\"glDrawArraysInstanced\"`. Unknown names are reported as not loaded.
+        #[allow(dead_code)]
+        pub fn alias_group_loaded(&self, name: &str) -> bool {{
+            Some(name).into_iter().chain(alias_group(name).iter().cloned())
+                .any(|name| self.fn_ptr_by_symbol(name).map_or(false, FnPtr::is_loaded))
+        }}
+
+        /// Returns `true` if the entry points of an extension (e.g. `&[\"glCreateQueries\", ...]`)
+        /// are usable, each through the function itself or another function of its alias group.
+        ///
+        /// Unlike `all_loaded`, this accepts the core functions for the functions of an extension
+        /// which was promoted to the core, and the other way around.
+        #[allow(dead_code)]
+        pub fn extension_functions_loaded(&self, names: &[&str]) -> bool {{
+            names.iter().all(|name| self.alias_group_loaded(name))
+        }}"
+    )?;
+
+    if gen.resolved_symbols {
+        writeln!(
+            dest,
+            "/// Returns the symbol which satisfied the load of the named function (e.g.
+            /// `\"glCreateQueries\"`), which is one of its fallbacks if the driver does not export
+            /// the function itself.
+            #[allow(dead_code)]
+            pub fn resolved_symbol(&self, name: &str) -> Option<&'static str> {{
+                self.fn_ptr_by_symbol(name).and_then(FnPtr::resolved_symbol)
+            }}"
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates the methods which drain the GL error flags on demand.
+///
+/// These are written inside the `impl` created by `write_impl`.
+pub(crate) fn write_error_poll_fns<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let (no_error, error_to_str) = gen_error_codes(registry.api);
+    writeln!(
+        dest,
+        "/// Calls `GetError` until it reports no error, pushing each error code with its
+        /// description into `out`. Each drained error is counted by the profiler.
+        ///
+        /// At most 64 errors are drained at once, so a lost context can not stall the caller.
+        #[allow(dead_code)]
+        pub fn poll_errors_into(&self, out: &mut Vec<(u32, &'static str)>) {{{pending}
+            for _ in 0..64 {{
+                let r = unsafe {{
+                    __gl_imports::mem::transmute::<_, extern \"system\" fn() -> u32>(self.GetError.f)()
+                }};
+                if r == {no_error} {{
+                    break;
+                }}
+                {inc_err}
+                out.push((r, {error_to_str}(r)));
+            }}
+        }}",
+        inc_err = if gen.instance_counters { "count_error(r); self.counters.inc_err();" } else { "count_error(r);" },
+        pending = if gen.has_error_passthrough(registry) {
+            format!(
+                "
+            // the pending error has been counted when it was found
+            if let Some(r) = take_pending_error() {{
+                out.push((r, {}(r)));
+            }}",
+                error_to_str
+            )
+        } else {
+            String::new()
+        },
+        no_error = no_error,
+        error_to_str = error_to_str
+    )?;
+
+    if gen.batched_errors {
+        writeln!(
+            dest,
+            "
+        /// Calls `GetError` until it reports no error, counting and logging each error, and
+        /// returns the number of errors found since the previous flush.
+        ///
+        /// At most 64 errors are drained at once, so a lost context can not stall the caller.
+        #[allow(dead_code)]
+        pub fn {profiler_flush_errors}(&self) -> usize {{
+            let mut errors = 0;
+            for _ in 0..64 {{
+                let r = unsafe {{
+                    __gl_imports::mem::transmute::<_, extern \"system\" fn() -> u32>(self.GetError.f)()
+                }};
+                if r == {no_error} {{
+                    break;
+                }}
+                {inc_err}
+                println!(\"{log_prefix} GL error since the last flush: {{}}, {{}}\", r, {error_to_str}(r));
+                errors += 1;
+            }}
+            errors
+        }}",
+            inc_err = if gen.instance_counters { "count_error(r); self.counters.inc_err();" } else { "count_error(r);" },
+            log_prefix = gen.log_prefix(registry),
+            no_error = no_error,
+            error_to_str = error_to_str,
+            profiler_flush_errors = gen.fn_name("flush_errors"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates the `profiler_self_benchmark(&self, iterations)` method.
+///
+/// This is written inside the `impl` created by `write_impl`.
+pub(crate) fn write_self_benchmark_fns<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let return_suffix = registry
+        .cmds
+        .iter()
+        .find(|cmd| cmd.proto.ident == "GetError")
+        .map(|cmd| cmd.proto.ty.to_string())
+        .unwrap_or_default();
+    writeln!(
+        dest,
+        r##"
+        /// Calls `GetError` `iterations` times through the generated method, and as many times
+        /// through the function pointer, and returns the rates of both, which shows the cost of
+        /// the profiling on this machine and in this build.
+        ///
+        /// A context must be current.
+        #[allow(dead_code)]
+        pub fn {profiler_self_benchmark}(&self, iterations: usize) -> SelfBenchmark {{
+            let start = ::std::time::Instant::now();
+            for _ in 0..iterations {{
+                ::std::hint::black_box(unsafe {{ self.GetError() }});
+            }}
+            let instrumented = start.elapsed();
+            let get_error = unsafe {{
+                __gl_imports::mem::transmute::<_, extern "system" fn() -> {return_suffix}>(self.GetError.f)
+            }};
+            let start = ::std::time::Instant::now();
+            for _ in 0..iterations {{
+                ::std::hint::black_box(get_error());
+            }}
+            let bare = start.elapsed();
+            let rate = |elapsed: ::std::time::Duration| iterations as f64 / elapsed.as_secs_f64().max(1e-9);
+            SelfBenchmark {{
+                iterations,
+                instrumented_calls_per_sec: rate(instrumented),
+                bare_calls_per_sec: rate(bare),
+            }}
+        }}"##,
+        return_suffix = return_suffix,
+        profiler_self_benchmark = gen.fn_name("self_benchmark"),
+    )
+}
+
+/// Creates the result of `profiler_self_benchmark`.
+pub(crate) fn write_self_benchmark_struct<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+/// The rates of the `GetError` calls measured by `{profiler_self_benchmark}`.
+#[derive(Clone, Copy, Debug)]
+pub struct SelfBenchmark {{
+    pub iterations: usize,
+    /// The calls per second through the generated method.
+    pub instrumented_calls_per_sec: f64,
+    /// The calls per second through the function pointer.
+    pub bare_calls_per_sec: f64,
+}}
+
+impl SelfBenchmark {{
+    /// Returns the time the profiling adds to each call, in nanoseconds.
+    pub fn overhead_ns_per_call(&self) -> f64 {{
+        1e9 / self.instrumented_calls_per_sec - 1e9 / self.bare_calls_per_sec
+    }}
+}}
+
+impl ::std::fmt::Display for SelfBenchmark {{
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+        write!(
+            f,
+            "{{:.0}} calls/s instrumented, {{:.0}} calls/s bare, {{:.1}}ns overhead per call",
+            self.instrumented_calls_per_sec,
+            self.bare_calls_per_sec,
+            self.overhead_ns_per_call()
+        )
+    }}
+}}
+"##,
+        profiler_self_benchmark = gen.fn_name("self_benchmark"),
+    )
+}
+
+/// Creates the method which enters a profiler scope and pushes a debug group.
+///
+/// This is written inside the `impl` created by `write_impl`.
+pub(crate) fn write_debug_group_fns<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+        /// Enters the named profiler scope, like the free `{profiler_scope}`, and pushes a debug
+        /// group of the same name, until the returned guard is dropped.
+        ///
+        /// The debug group is skipped if `glPushDebugGroup` is not loaded.
+        #[allow(dead_code)]
+        pub fn {profiler_scope}(&self, name: &'static str) -> DebugGroupScope<'_> {{
+            if self.PushDebugGroup.is_loaded() {{
+                unsafe {{
+                    __gl_imports::mem::transmute::<_, extern "system" fn(u32, u32, i32, *const __gl_imports::raw::c_char)>
+                        (self.PushDebugGroup.f)(0x824A, 0, name.len() as i32, name.as_ptr() as *const _)
+                }}
+            }}
+            DebugGroupScope {{ gl: self, _scope: enter_scope(name) }}
+        }}
+"##,
+        profiler_scope = gen.fn_name("scope"),
+    )
+}
+
+/// Creates the method which labels GL objects.
+///
+/// This is written inside the `impl` created by `write_impl`.
+pub(crate) fn write_object_label_fns<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+        /// Labels the object `name` of the type `identifier` (e.g. `BUFFER` or `TEXTURE`) with
+        /// `glObjectLabel`, or does nothing if `glObjectLabel` is not loaded.
+        ///
+        /// The call is not counted by the profiler.
+        #[allow(dead_code)]
+        pub fn label_object(&self, identifier: u32, name: u32, label: &str) {
+            if self.ObjectLabel.is_loaded() {
+                unsafe {
+                    __gl_imports::mem::transmute::<_, extern "system" fn(u32, u32, i32, *const __gl_imports::raw::c_char)>
+                        (self.ObjectLabel.f)(identifier, name, label.len() as i32, label.as_ptr() as *const _)
+                }
+            }
+        }
+"##
+        .as_bytes(),
+    )
+}
+
+/// Creates the method which captures the GL vendor, renderer and version strings.
+///
+/// This is written inside the `impl` created by `write_impl`.
+pub(crate) fn write_gl_info_fns<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+        /// Reads the `GL_VENDOR`, `GL_RENDERER` and `GL_VERSION` strings with `glGetString` and
+        /// keeps them for `{profiler_gl_info}()`.
+        ///
+        /// Needs a current context. Nothing is kept if `glGetString` is not loaded or returns
+        /// null. The calls are not counted by the profiler.
+        #[allow(dead_code)]
+        pub fn {profiler_capture_gl_info}(&self) {{
+            if !self.GetString.is_loaded() {{
+                return;
+            }}
+            let get_string = |name: u32| {{
+                let s = unsafe {{
+                    __gl_imports::mem::transmute::<_, extern "system" fn(u32) -> *const u8>(self.GetString.f)(name)
+                }};
+                if s.is_null() {{
+                    None
+                }} else {{
+                    let s = unsafe {{ ::std::ffi::CStr::from_ptr(s as *const __gl_imports::raw::c_char) }};
+                    Some(s.to_string_lossy().into_owned())
+                }}
+            }};
+            if let (Some(vendor), Some(renderer), Some(version)) = (get_string(0x1F00), get_string(0x1F01), get_string(0x1F02)) {{
+                set_gl_info((vendor, renderer, version));
+            }}
+        }}
+"##,
+        profiler_gl_info = gen.fn_name("gl_info"),
+        profiler_capture_gl_info = gen.fn_name("capture_gl_info"),
+    )
+}
+
+/// Creates the method which probes the commands usable in the current context.
+///
+/// This is written inside the `impl` created by `write_impl`.
+pub(crate) fn write_usability_probe_fns<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+        /// Marks each command as usable in the current context if it is loaded and, for the
+        /// commands of a later version of the API, if `glGetString(GL_VERSION)` reports that
+        /// version or a newer one. The marks are read with `{profiler_command_usable}(name)`.
+        ///
+        /// Needs a current context, and does nothing if `glGetString` is not loaded or returns
+        /// null. The call is not counted by the profiler.
+        #[allow(dead_code)]
+        pub fn {profiler_probe_usable}(&self) {{
+            if !self.GetString.is_loaded() {{
+                return;
+            }}
+            let version = unsafe {{
+                __gl_imports::mem::transmute::<_, extern "system" fn(u32) -> *const u8>(self.GetString.f)(0x1F02)
+            }};
+            if version.is_null() {{
+                return;
+            }}
+            let version = unsafe {{ ::std::ffi::CStr::from_ptr(version as *const __gl_imports::raw::c_char) }};
+            mark_usable_commands(&version.to_string_lossy(), |index| {{
+                self.fn_ptr_by_symbol(COMMAND_NAMES[index]).map_or(false, FnPtr::is_loaded)
+            }});
+        }}
+"##,
+        profiler_command_usable = gen.fn_name("command_usable"),
+        profiler_probe_usable = gen.fn_name("probe_usable"),
+    )
+}
+
+/// Creates the guard returned by the `profiler_scope(&self, name)` method, which pops the debug
+/// group.
+pub(crate) fn write_debug_group_scope<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+/// A profiler scope which is also a GL debug group, created by the `{profiler_scope}(&self, name)`
+/// method.
+#[must_use]
+pub struct DebugGroupScope<'a> {{
+    gl: &'a {api},
+    _scope: ProfilerScope,
+}}
+
+impl Drop for DebugGroupScope<'_> {{
+    fn drop(&mut self) {{
+        if self.gl.PopDebugGroup.is_loaded() {{
+            unsafe {{ __gl_imports::mem::transmute::<_, extern "system" fn()>(self.gl.PopDebugGroup.f)() }}
+        }}
+    }}
+}}"##,
+        api = generators::gen_struct_name(registry.api),
+        profiler_scope = gen.fn_name("scope"),
+    )
+}
+
+/// Creates the methods which access the counters of the instance.
+///
+/// These are written inside the `impl` created by `write_impl`.
+pub(crate) fn write_instance_counter_fns<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+        /// Returns the number of calls made through this instance since it was created or its
+        /// counters were reset.
+        #[allow(dead_code)]
+        pub fn call_count(&self) -> usize {
+            self.counters.calls.load(::std::sync::atomic::Ordering::SeqCst)
+        }
+
+        /// Returns the number of errors of the calls made through this instance since it was
+        /// created or its counters were reset.
+        #[allow(dead_code)]
+        pub fn err_count(&self) -> usize {
+            self.counters.errors.load(::std::sync::atomic::Ordering::SeqCst)
+        }
+
+        /// Resets the counters of this instance only.
+        #[allow(dead_code)]
+        pub fn reset_counters(&self) {
+            self.counters.calls.store(0, ::std::sync::atomic::Ordering::SeqCst);
+            self.counters.errors.store(0, ::std::sync::atomic::Ordering::SeqCst);
+        }
+
+        /// Moves the bindings into an `Arc`, to share them, and their counters, between threads.
+        #[allow(dead_code)]
+        pub fn shared(self) -> ::std::sync::Arc<Self> {
+            ::std::sync::Arc::new(self)
+        }
+"##
+        .as_bytes(),
+    )
+}
+
+/// Creates the methods which insert and collect the timing fences.
+///
+/// These are written inside the `impl` created by `write_impl`.
+pub(crate) fn write_gpu_fence_fns<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+        fn insert_timing_fence(&self, index: usize) {{{dry_run_return}
+            let mut pending = PENDING_FENCES.lock().unwrap_or_else(|e| e.into_inner());
+            if pending.len() >= MAX_PENDING_FENCES || !self.FenceSync.is_loaded() {{
+                return;
+            }}
+            let sync = unsafe {{
+                __gl_imports::mem::transmute::<_, extern "system" fn(u32, u32) -> *const __gl_imports::raw::c_void>
+                    (self.FenceSync.f)(0x9117, 0)
+            }};
+            if !sync.is_null() {{
+                pending.push((index, sync as usize, ::std::time::Instant::now()));
+            }}
+        }}
+
+        /// Polls the pending timing fences without waiting, and adds the time from the insertion
+        /// of each signalled one until now to the fence latency of the fenced command.
+        ///
+        /// The queue of fences is shared by all the instances, so this should be called with the
+        /// context which inserted them current.
+        ///
+        /// The results lag behind: a fence is only seen completed by a call made after the GPU has
+        /// caught up with it, typically one to three frames later. The precision is also bounded
+        /// by how often this is called, so it should be called once per frame, e.g. after the
+        /// buffers are swapped.
+        #[allow(dead_code)]
+        pub fn {profiler_collect_fence_latencies}(&self) {{{dry_run_return}
+            let mut pending = PENDING_FENCES.lock().unwrap_or_else(|e| e.into_inner());
+            let mut completed = 0;
+            for &(index, sync, inserted) in pending.iter() {{
+                let sync = sync as *const __gl_imports::raw::c_void;
+                let status = unsafe {{
+                    __gl_imports::mem::transmute::<_, extern "system" fn(*const __gl_imports::raw::c_void, u32, u64) -> u32>
+                        (self.ClientWaitSync.f)(sync, 0, 0)
+                }};
+                match status {{
+                    // ALREADY_SIGNALED, CONDITION_SATISFIED
+                    0x911A | 0x911C => {{
+                        FENCE_LATENCY_NS[index].fetch_add(
+                            inserted.elapsed().as_nanos() as u64,
+                            ::std::sync::atomic::Ordering::SeqCst,
+                        );
+                    }}
+                    // TIMEOUT_EXPIRED: the fences complete in order, so the rest are pending too
+                    0x911B => break,
+                    // WAIT_FAILED: the fence is dropped
+                    _ => {{}}
+                }}
+                unsafe {{
+                    __gl_imports::mem::transmute::<_, extern "system" fn(*const __gl_imports::raw::c_void)>
+                        (self.DeleteSync.f)(sync)
+                }};
+                completed += 1;
+            }}
+            pending.drain(..completed);
+        }}
+"##,
+        profiler_collect_fence_latencies = gen.fn_name("collect_fence_latencies"),
+        // the fences are GL calls too
+        dry_run_return = if gen.dry_run {
+            "
+            if dry_run() {
+                return;
+            }"
+        } else {
+            ""
+        },
+    )
+}
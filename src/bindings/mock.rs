@@ -0,0 +1,97 @@
+//! Writers of the mock functions.
+
+use gl_generator::Registry;
+use std::io;
+
+use crate::util::gen_parameters;
+
+/// Creates the conversion of the values returned by the mock stubs and the dry runs.
+pub(crate) fn write_mock_value_trait<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+/// A value which can be returned by the mock stubs and the dry runs.
+trait MockValue {
+    fn mock_value(value: i64) -> Self;
+}
+
+impl MockValue for () {
+    fn mock_value(_: i64) {}
+}
+
+macro_rules! impl_mock_value {
+    ($($ty:ty),*) => {
+        $(impl MockValue for $ty {
+            fn mock_value(value: i64) -> $ty {
+                value as $ty
+            }
+        })*
+    };
+}
+
+impl_mock_value!(i8, u8, i16, u16, i32, u32, i64, u64, isize, usize, f32, f64);
+
+impl<T> MockValue for *const T {
+    fn mock_value(value: i64) -> *const T {
+        value as usize as *const T
+    }
+}
+
+impl<T> MockValue for *mut T {
+    fn mock_value(value: i64) -> *mut T {
+        value as usize as *mut T
+    }
+}
+
+impl MockValue for extern "system" fn() {
+    fn mock_value(_: i64) -> extern "system" fn() {
+        extern "system" fn nop() {}
+        nop
+    }
+}
+"##
+        .as_bytes(),
+    )
+}
+
+/// Creates the stubs which the struct created by `mock()` calls instead of the loaded functions.
+pub(crate) fn write_mock_fns<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        "thread_local! {{
+            /// The values returned by the mock stubs on this thread, in the order of
+            /// `command_names()`. The stubs of the commands without a value return zero.
+            static MOCK_RETURNS: ::std::cell::RefCell<[Option<i64>; {count}]> = const {{ ::std::cell::RefCell::new([None; {count}]) }};
+        }}
+
+        fn mock_return(index: usize) -> i64 {{
+            MOCK_RETURNS.with(|returns| returns.borrow()[index].unwrap_or(0))
+        }}",
+        count = registry.cmds.len()
+    )?;
+
+    for (index, cmd) in registry.cmds.iter().enumerate() {
+        writeln!(
+            dest,
+            "#[allow(non_snake_case)]
+            extern \"system\" fn mock_{name}({params}) -> {return_suffix} {{
+                MockValue::mock_value(mock_return({index}))
+            }}",
+            name = cmd.proto.ident,
+            index = index,
+            params = gen_parameters(cmd, false, true)
+                .iter()
+                .map(|ty| format!("_: {}", ty))
+                .collect::<Vec<_>>()
+                .join(", "),
+            return_suffix = cmd.proto.ty
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,441 @@
+//! Writers of the bindings around the struct: the types, the enums and the command metadata.
+
+pub(crate) mod methods;
+pub(crate) mod mock;
+pub(crate) mod structs;
+
+use gl_generator::{Registry, generators};
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+use crate::util::gen_profile_name;
+
+/// Creates a `__gl_imports` module which contains all the external symbols that we need for the
+///  bindings.
+pub(crate) fn write_header<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r#"
+        mod __gl_imports {{
+            #![allow(unused_imports)]
+            pub use std::mem;
+            pub use std::marker::Send;
+            pub use std::ptr;
+            pub use std::os::raw;
+        }}
+    "#
+    )
+}
+
+/// Creates a `types` module which contains all the type aliases.
+///
+/// See also `generators::gen_types`.
+pub(crate) fn write_type_aliases<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r#"
+        pub mod types {{
+            #![allow(non_camel_case_types, non_snake_case, dead_code, missing_copy_implementations)]
+    "#
+    )?;
+
+    generators::gen_types(registry.api, dest)?;
+
+    writeln!(dest, "}}")
+}
+
+/// Creates all the `<enum>` elements at the root of the bindings.
+pub(crate) fn write_enums<W>(registry: &Registry, types_prefix: &str, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    for enm in &registry.enums {
+        generators::gen_enum_item(enm, types_prefix, dest)?;
+    }
+
+    Ok(())
+}
+
+/// Creates the name tables of the enum groups used by the `GLenum` parameters, and `EnumArg`,
+/// which logs an argument by its name.
+pub(crate) fn write_enum_names<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let groups = gen.enum_groups(registry);
+    if groups.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        dest,
+        r#"/// A `GLenum` argument, logged by its name if it is in the table of its group.
+        #[allow(dead_code)]
+        struct EnumArg(u32, &'static [(u32, &'static str)]);
+        impl ::std::fmt::Debug for EnumArg {{
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+                match self.1.iter().find(|&&(value, _)| value == self.0) {{
+                    Some(&(_, name)) => f.write_str(name),
+                    None => write!(f, "{{:?}}", self.0),
+                }}
+            }}
+        }}"#
+    )?;
+    for (group, enums) in &groups {
+        writeln!(
+            dest,
+            "#[allow(non_upper_case_globals, dead_code)]
+            static ENUMS_{group}: &[(u32, &str)] = &[{enums}];",
+            group = group,
+            enums = enums
+                .iter()
+                .map(|name| format!("({}, \"GL_{}\")", name, name.trim_start_matches('_')))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+    if gen.enum_validation {
+        writeln!(
+            dest,
+            r#"/// Logs an argument which is not in the group of enums of its parameter.
+            #[cold]
+            #[inline(never)]
+            fn report_invalid_enum(command: &str, param: &str, value: u32) {{
+                println!("{} invalid enum 0x{{:X}} for {{}} {{}}", value, command, param);
+            }}"#,
+            gen.log_prefix(registry)
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates the constants which describe the API the bindings were generated for.
+pub(crate) fn write_api_info<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        "/// The API the bindings were generated for.
+        #[allow(dead_code)]
+        pub const API: &str = \"{api}\";",
+        api = registry.api
+    )?;
+
+    if let Some(((major, minor), profile)) = gen.api_version {
+        writeln!(
+            dest,
+            "/// The API version the bindings were generated for.
+            #[allow(dead_code)]
+            pub const API_VERSION: (u8, u8) = ({major}, {minor});
+            /// The API profile the bindings were generated for.
+            #[allow(dead_code)]
+            pub const API_PROFILE: &str = \"{profile}\";",
+            major = major,
+            minor = minor,
+            profile = gen_profile_name(profile)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates the list of the native symbol names of all commands, in the order of the struct fields.
+///
+/// The list and its length are constants, so they can be used to build tables at compile time.
+pub(crate) fn write_command_names<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        "/// The number of commands.
+        #[allow(dead_code)]
+        pub const COMMAND_COUNT: usize = {count};
+
+        /// The native symbol names of all commands, in the order of the struct fields.
+        #[allow(dead_code)]
+        pub const COMMAND_NAMES: &[&str; COMMAND_COUNT] = &[",
+        count = registry.cmds.len()
+    )?;
+
+    // the registry orders the commands by name, which `command_index` relies on
+    let symbols: Vec<_> = registry
+        .cmds
+        .iter()
+        .map(|cmd| generators::gen_symbol_name(registry.api, &cmd.proto.ident))
+        .collect();
+    debug_assert!(symbols.windows(2).all(|w| w[0] < w[1]));
+    for symbol in &symbols {
+        writeln!(dest, "\"{symbol}\",", symbol = symbol)?;
+    }
+
+    writeln!(
+        dest,
+        "];
+
+        /// Returns the native symbol names of all commands, in the order of the struct fields.
+        #[allow(dead_code)]
+        pub const fn command_names() -> &'static [&'static str] {{
+            COMMAND_NAMES
+        }}
+
+        /// Returns the index of the named command in `COMMAND_NAMES`, which is sorted.
+        #[allow(dead_code)]
+        fn command_index(name: &str) -> Option<usize> {{
+            COMMAND_NAMES.binary_search(&name).ok()
+        }}
+
+        /// Returns the fallback symbols which are tried when the named command (e.g.
+        /// `\"glCreateQueries\"`) can not be loaded.
+        #[allow(dead_code)]
+        pub fn fallbacks_for(name: &str) -> &'static [&'static str] {{
+            match command_index(name) {{"
+    )?;
+
+    for (index, cmd) in registry.cmds.iter().enumerate() {
+        if let Some(fbs) = registry.aliases.get(&cmd.proto.ident) {
+            writeln!(
+                dest,
+                "Some({index}) => &[{fallbacks}],",
+                index = index,
+                fallbacks = fbs.iter()
+                    .map(|name| format!("\"{}\"", generators::gen_symbol_name(registry.api, name)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )?;
+        }
+    }
+
+    writeln!(
+        dest,
+        "_ => &[],
+            }}
+        }}
+
+        /// Returns the alias group of the named command (e.g. `\"glDrawArraysInstanced\"` and
+        /// `\"glDrawArraysInstancedARB\"`): the core command first, then the commands of the
+        /// extensions which alias it. The group is empty for a command without aliases.
+        #[allow(dead_code)]
+        pub fn alias_group(name: &str) -> &'static [&'static str] {{
+            match command_index(name) {{"
+    )?;
+
+    for (index, cmd) in registry.cmds.iter().enumerate() {
+        let core = cmd.alias.as_ref().unwrap_or(&cmd.proto.ident);
+        if let Some(aliases) = registry.aliases.get(core) {
+            let group: Vec<_> = Some(core).into_iter().chain(aliases)
+                .map(|name| format!("\"{}\"", generators::gen_symbol_name(registry.api, name)))
+                .collect();
+            writeln!(dest, "Some({index}) => &[{group}],", index = index, group = group.join(", "))?;
+        }
+    }
+
+    writeln!(
+        dest,
+        "_ => &[],
+            }}
+        }}"
+    )
+}
+
+/// Creates a `FnPtr` structure which contains the store for a single binding.
+pub(crate) fn write_fnptr_struct_def<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    if gen.compact_fnptr {
+        return dest.write_all(
+            b"
+        #[allow(dead_code, missing_copy_implementations)]
+        #[derive(Clone)]
+        #[repr(transparent)]
+        pub struct FnPtr {
+            /// The function pointer that will be used when calling the function, which points to
+            /// `missing_fn_panic` if the function has not been loaded.
+            f: *const __gl_imports::raw::c_void,
+        }
+        impl FnPtr {
+            /// Creates a `FnPtr` from a load attempt.
+            fn new(ptr: *const __gl_imports::raw::c_void) -> FnPtr {
+                if ptr.is_null() {
+                    FnPtr { f: missing_fn_panic as *const __gl_imports::raw::c_void }
+                } else {
+                    FnPtr { f: ptr }
+                }
+            }
+            /// Returns `true` if the function has been successfully loaded.
+            ///
+            /// If it returns `false`, calling the corresponding function will fail.
+            #[inline]
+            #[allow(dead_code)]
+            pub fn is_loaded(&self) -> bool {
+                self.f != missing_fn_panic as *const __gl_imports::raw::c_void
+            }
+        }
+    ",
+        );
+    }
+    writeln!(
+        dest,
+        "
+        #[allow(dead_code, missing_copy_implementations)]
+        #[derive(Clone)]
+        {repr}
+        pub struct FnPtr {{
+            /// The function pointer that will be used when calling the function.
+            f: *const __gl_imports::raw::c_void,
+            /// True if the pointer points to a real function, false if points to a `panic!` fn.
+            is_loaded: bool,{symbol_field}
+        }}
+        impl FnPtr {{
+            /// Creates a `FnPtr` from a load attempt.
+            fn new(ptr: *const __gl_imports::raw::c_void) -> FnPtr {{
+                if ptr.is_null() {{
+                    FnPtr {{
+                        f: missing_fn_panic as *const __gl_imports::raw::c_void,
+                        is_loaded: false{symbol_init}
+                    }}
+                }} else {{
+                    FnPtr {{ f: ptr, is_loaded: true{symbol_init} }}
+                }}
+            }}
+            /// Returns `true` if the function has been successfully loaded.
+            ///
+            /// If it returns `false`, calling the corresponding function will fail.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn is_loaded(&self) -> bool {{
+                self.is_loaded
+            }}{symbol_fns}
+        }}
+    ",
+        repr = if gen.repr_c { "#[repr(C)]" } else { "" },
+        symbol_field = if gen.resolved_symbols {
+            "
+            /// The symbol which satisfied the load, if the function has been loaded.
+            symbol: Option<&'static str>,"
+        } else {
+            ""
+        },
+        symbol_init = if gen.resolved_symbols { ", symbol: None" } else { "" },
+        symbol_fns = if gen.resolved_symbols {
+            "
+            /// Creates a `FnPtr` from a load attempt which reports the symbol it has tried last.
+            fn resolved((ptr, symbol): (*const __gl_imports::raw::c_void, &'static str)) -> FnPtr {
+                let mut fn_ptr = FnPtr::new(ptr);
+                if fn_ptr.is_loaded {
+                    fn_ptr.symbol = Some(symbol);
+                }
+                fn_ptr
+            }
+            /// Returns the symbol which satisfied the load, if the function has been loaded.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn resolved_symbol(&self) -> Option<&'static str> {
+                self.symbol
+            }"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Creates a `panicking` module which contains one function per GL command.
+///
+/// These functions are the mocks that are called if the real function could not be loaded.
+pub(crate) fn write_panicking_fns<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        "#[inline(never)]
+        fn missing_fn_panic() -> ! {{
+            panic!(\"{api} function was not loaded\")
+        }}",
+        api = registry.api
+    )
+}
+
+/// Converts the name of a command to snake case, e.g. `TexImage2D` to `tex_image2d`.
+pub(crate) fn gen_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if previous.is_ascii_lowercase() || (next_lower && previous.is_ascii_alphanumeric()) {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_generator::{Api, Fallbacks, Profile, Registry};
+    use std::collections::BTreeMap;
+
+    use super::{gen_snake_case, write_command_names};
+
+    fn registries() -> Vec<Registry> {
+        vec![
+            Registry::new(Api::Gl, (4, 6), Profile::Compatibility, Fallbacks::All, []),
+            Registry::new(Api::Gles2, (3, 2), Profile::Core, Fallbacks::All, []),
+            Registry::new(Api::Egl, (1, 5), Profile::Core, Fallbacks::All, []),
+            Registry::new(Api::Glx, (1, 4), Profile::Core, Fallbacks::All, []),
+            Registry::new(Api::Wgl, (1, 0), Profile::Core, Fallbacks::All, []),
+        ]
+    }
+
+    #[test]
+    fn snake_case() {
+        assert_eq!(gen_snake_case("DrawArrays"), "draw_arrays");
+        assert_eq!(gen_snake_case("GetnUniformfv"), "getn_uniformfv");
+        assert_eq!(gen_snake_case("TexImage2D"), "tex_image2d");
+        assert_eq!(gen_snake_case("VertexAttribIPointer"), "vertex_attrib_i_pointer");
+        assert_eq!(gen_snake_case("GetQueryObjectui64v"), "get_query_objectui64v");
+        // the names which only differ by an underscore collide
+        assert_eq!(gen_snake_case("Clear_color"), gen_snake_case("ClearColor"));
+    }
+
+    #[test]
+    fn snake_case_names_are_unique_in_the_registries() {
+        for registry in registries() {
+            let mut names = BTreeMap::new();
+            for cmd in &registry.cmds {
+                if let Some(other) = names.insert(gen_snake_case(&cmd.proto.ident), &cmd.proto.ident) {
+                    panic!("`{}` and `{}` collide in {:?}", other, cmd.proto.ident, registry.api);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn command_names_are_sorted_for_the_binary_search() {
+        for registry in registries() {
+            let mut dest = Vec::new();
+            write_command_names(&registry, &mut dest).unwrap();
+            let code = String::from_utf8(dest).unwrap();
+            let start = code.find("pub const COMMAND_NAMES").unwrap();
+            let names: Vec<&str> = code[start..]
+                .lines()
+                .skip(1)
+                .take_while(|line| !line.starts_with("];"))
+                .map(|line| line.trim().trim_end_matches(',').trim_matches('"'))
+                .collect();
+            assert_eq!(names.len(), registry.cmds.len(), "{:?}", registry.api);
+            assert!(names.windows(2).all(|w| w[0] < w[1]), "{:?}", registry.api);
+            assert!(code.contains("COMMAND_NAMES.binary_search(&name)"));
+        }
+    }
+}
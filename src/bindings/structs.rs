@@ -0,0 +1,828 @@
+//! Writers of the struct, its `impl` and the structs and instances built on it.
+
+use gl_generator::{Registry, generators};
+use std::io;
+
+use crate::bindings::gen_snake_case;
+use crate::bindings::methods::{write_assert_macro, write_debug_group_fns, write_debug_group_scope, write_error_poll_fns, write_gl_info_fns, write_gpu_fence_fns, write_instance_counter_fns, write_lookup_fns, write_object_label_fns, write_self_benchmark_fns, write_self_benchmark_struct, write_usability_probe_fns};
+use crate::builder::ProfilingStructGenerator;
+use crate::helpers::errors::gen_error_codes;
+use crate::util::{enum_group, gen_doc_link, gen_parameters, has_get_error};
+
+/// Creates a structure which stores all the `FnPtr` of the bindings.
+///
+/// The name of the struct corresponds to the namespace.
+pub(crate) fn write_struct<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    if gen.is_send() {
+        writeln!(dest, "{}", SEND_DOC)?;
+        if gen.repr_c {
+            writeln!(dest, "///")?;
+        }
+    }
+    if gen.repr_c {
+        writeln!(
+            dest,
+            "/// The layout is `#[repr(C)]`: one `FnPtr` per command, in the order of
+            /// `command_names()`, followed by a zero-sized private field.
+            #[repr(C)]"
+        )?;
+    }
+    writeln!(
+        dest,
+        "{must_use}
+        #[allow(non_camel_case_types, non_snake_case, dead_code)]
+        #[derive(Clone)]
+        pub struct {api} {{",
+        must_use = gen.must_use_attr(),
+        api = generators::gen_struct_name(registry.api)
+    )?;
+
+    for cmd in &registry.cmds {
+        if let Some(v) = registry.aliases.get(&cmd.proto.ident) {
+            writeln!(dest, "/// Fallbacks: {}", v.join(", "))?;
+        }
+        writeln!(dest, "pub {name}: FnPtr,", name = cmd.proto.ident)?;
+    }
+    writeln!(dest, "{}", gen.gen_private_fields(false))?;
+
+    writeln!(dest, "}}")?;
+    if gen.has_cached_get_error(registry) {
+        writeln!(
+            dest,
+            "/// The `GetError` function of the last loaded bindings, called by the error checks.
+            static GET_ERROR_FN: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);"
+        )?;
+    }
+    Ok(())
+}
+
+/// The documentation of the generated structs which implement `Send`.
+pub(crate) const SEND_DOC: &str = "/// The struct is `Send`: the function pointers returned by the loader are valid in every thread
+/// of the process. A GL context must still be current on the thread which calls a function.";
+
+/// Creates the `impl` of the structure created by `write_struct`.
+pub(crate) fn write_impl<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(dest,
+                  "impl {api} {{
+            /// Load each OpenGL symbol using a custom load function. This allows for the
+            /// use of functions like `glfwGetProcAddress` or `SDL_GL_GetProcAddress`.
+            ///
+            /// ~~~ignore
+            /// let gl = Gl::load_with(|s| glfw.get_proc_address(s));
+            /// ~~~
+            #[allow(dead_code, unused_variables)]
+            pub fn load_with<F>(mut loadfn: F) -> {api} where F: FnMut(&'static str) -> *const __gl_imports::raw::c_void {{
+                #[inline(never)]
+                {do_metaloadfn}
+                let mut metaloadfn = |symbol: &'static str, symbols: &[&'static str]| {{
+                    do_metaloadfn(&mut loadfn, symbol, symbols)
+                }};{warning}
+                {open}{api} {{",
+                  api = generators::gen_struct_name(registry.api),
+                  open = gen.gen_construction(registry).0,
+                  warning = if gen.error_checking && !has_get_error(registry) {
+                      // otherwise the error count stays at zero without an explanation
+                      format!(
+                          "
+                static WARNED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+                if !WARNED.swap(true, ::std::sync::atomic::Ordering::Relaxed) {{
+                    println!(\"{} error checking is inactive: the {} bindings have no GetError command\");
+                }}",
+                          gen.log_prefix(registry),
+                          registry.api
+                      )
+                  } else {
+                      String::new()
+                  },
+                  do_metaloadfn = if gen.resolved_symbols {
+                      "fn do_metaloadfn(loadfn: &mut dyn FnMut(&'static str) -> *const __gl_imports::raw::c_void,
+                                 symbol: &'static str,
+                                 symbols: &[&'static str])
+                                 -> (*const __gl_imports::raw::c_void, &'static str) {
+                    let mut ptr = loadfn(symbol);
+                    let mut resolved = symbol;
+                    if ptr.is_null() {
+                        for &sym in symbols {
+                            ptr = loadfn(sym);
+                            resolved = sym;
+                            if !ptr.is_null() { break; }
+                        }
+                    }
+                    (ptr, resolved)
+                }"
+                  } else {
+                      "fn do_metaloadfn(loadfn: &mut dyn FnMut(&'static str) -> *const __gl_imports::raw::c_void,
+                                 symbol: &'static str,
+                                 symbols: &[&'static str])
+                                 -> *const __gl_imports::raw::c_void {
+                    let mut ptr = loadfn(symbol);
+                    if ptr.is_null() {
+                        for &sym in symbols {
+                            ptr = loadfn(sym);
+                            if !ptr.is_null() { break; }
+                        }
+                    }
+                    ptr
+                }"
+                  })?;
+
+    for cmd in &registry.cmds {
+        writeln!(
+            dest,
+            "{name}: FnPtr::{constructor}(metaloadfn(\"{symbol}\", &[{fallbacks}])),",
+            name = cmd.proto.ident,
+            constructor = if gen.resolved_symbols { "resolved" } else { "new" },
+            symbol = generators::gen_symbol_name(registry.api, &cmd.proto.ident),
+            fallbacks = match registry.aliases.get(&cmd.proto.ident) {
+                Some(fbs) => fbs.iter()
+                    .map(|name| format!("\"{}\"", generators::gen_symbol_name(registry.api, name)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => String::new(),
+            },
+        )?
+    }
+    writeln!(dest, "{}", gen.gen_private_fields(true))?;
+
+    writeln!(
+        dest,
+        "}}{close}
+        }}",
+        close = gen.gen_construction(registry).1
+    )?;
+
+    writeln!(
+        dest,
+        "/// Creates the bindings without loading any function, e.g. to hold them before the
+        /// context exists. Every function is reported as not loaded, and panics if called.
+        #[allow(dead_code)]
+        pub fn unloaded() -> {api} {{
+            {api} {{",
+        api = generators::gen_struct_name(registry.api)
+    )?;
+    for cmd in &registry.cmds {
+        writeln!(dest, "{name}: FnPtr::new(__gl_imports::ptr::null()),", name = cmd.proto.ident)?;
+    }
+    writeln!(
+        dest,
+        "{private_fields}
+            }}
+        }}
+
+        /// Returns the size of the struct in bytes, which is mostly made of the `COMMAND_COUNT`
+        /// function pointers, e.g. to account for the memory of the bindings.
+        #[allow(dead_code)]
+        pub const fn table_size_bytes() -> usize {{
+            __gl_imports::mem::size_of::<{api}>()
+        }}",
+        private_fields = gen.gen_private_fields(true),
+        api = generators::gen_struct_name(registry.api)
+    )?;
+
+    if gen.mock {
+        writeln!(
+            dest,
+            "/// Creates the bindings with stubs in place of the GL functions. The stubs do nothing
+            /// and return zero, or the value set with `returning`, but the calls are counted by
+            /// the profiler as usual.
+            ///
+            /// The values set with `returning` are kept per thread, see `returning`.
+            #[allow(dead_code)]
+            pub fn mock() -> {api} {{
+                {open}{api} {{",
+            open = gen.gen_construction(registry).0,
+            api = generators::gen_struct_name(registry.api)
+        )?;
+        for cmd in &registry.cmds {
+            writeln!(
+                dest,
+                "{name}: FnPtr::new(mock_{name} as *const __gl_imports::raw::c_void),",
+                name = cmd.proto.ident
+            )?;
+        }
+        writeln!(
+            dest,
+            "{private_fields}
+                }}{close}
+            }}
+
+            /// Makes the stub of the named command (e.g. `\"glGetUniformLocation\"`) return
+            /// `value`, converted to the return type of the command.
+            ///
+            /// The stubs can not tell the mocks apart, so the value is kept for the current
+            /// thread, and returned to all the mocks called on it until it is set again. Each
+            /// `#[test]` runs on its own thread, so the tests do not see each other's values.
+            ///
+            /// Panics if there is no such command.
+            #[allow(dead_code)]
+            pub fn returning(self, name: &str, value: i64) -> Self {{
+                let index = command_index(name)
+                    .unwrap_or_else(|| panic!(\"unknown command {{}}\", name));
+                MOCK_RETURNS.with(|returns| returns.borrow_mut()[index] = Some(value));
+                self
+            }}",
+            private_fields = gen.gen_private_fields(true),
+            close = gen.gen_construction(registry).1
+        )?;
+    }
+
+    write_lookup_fns(gen, registry, dest)?;
+    if has_get_error(registry) {
+        write_error_poll_fns(gen, registry, dest)?;
+    }
+    if gen.has_gpu_fence_timing(registry) {
+        write_gpu_fence_fns(gen, dest)?;
+    }
+
+    let log_prefix = gen.log_prefix(registry);
+    let (no_error, _) = gen_error_codes(registry.api);
+    let enum_groups = gen.enum_groups(registry);
+    let (_, categories) = gen.categories(registry);
+    let mut last_args_offset = 0;
+
+    for (index, cmd) in registry.cmds.iter().enumerate() {
+        let idents = gen_parameters(cmd, true, false);
+        let typed_params = gen_parameters(cmd, false, true);
+        let call_format = format!(
+            "{} {}({})",
+            log_prefix,
+            cmd.proto.ident,
+            (0..idents.len())
+                .map(|_| "{:?}".to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let call_args = idents
+            .iter()
+            .zip(typed_params.iter())
+            .zip(cmd.params.iter())
+            .map(|((name, ty), binding)| if ty.contains("GLDEBUGPROC") {
+                ", \"<callback>\"".to_string()
+            } else {
+                match enum_group(registry, binding) {
+                    Some(group) if gen.enum_names && enum_groups.contains_key(group.ident.as_str()) => {
+                        format!(", EnumArg({}, ENUMS_{})", name, group.ident)
+                    }
+                    _ => format!(", {}", name),
+                }
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        let println = format!("println!(\"{}\" {});", call_format, call_args);
+
+        let symbol = generators::gen_symbol_name(registry.api, &cmd.proto.ident);
+
+        // the errors are counted by `report_error`, except for the counters of the command and
+        // of the instance
+        let (inc_call, inc_err) = if gen.per_command_counts {
+            (
+                format!("{{ inc_call(); inc_command_call({}); }}", index),
+                format!("inc_command_err({});", index),
+            )
+        } else {
+            ("inc_call()".to_string(), String::new())
+        };
+        let inc_call = match categories[index] {
+            Some(category) if gen.category_counts => {
+                format!("{{ {}; inc_category_call({}); }}", inc_call, category)
+            }
+            _ => inc_call,
+        };
+        let (inc_call, inc_err) = if gen.instance_counters {
+            (
+                format!("{{ {}; self.counters.inc_call(); }}", inc_call),
+                format!("{} self.counters.inc_err();", inc_err),
+            )
+        } else {
+            (inc_call, inc_err)
+        };
+
+        let mut before_call = Vec::new();
+        if gen.trace_capacity > 0 {
+            before_call.push(format!("record_trace(\"{}\");", symbol));
+        }
+        if gen.enum_validation {
+            for (name, binding) in idents.iter().zip(cmd.params.iter()) {
+                match enum_group(registry, binding) {
+                    Some(group) if enum_groups.contains_key(group.ident.as_str()) => {
+                        before_call.push(format!(
+                            "if !ENUMS_{group}.iter().any(|&(value, _)| value == {name}) {{ report_invalid_enum(\"{symbol}\", \"{param}\", {name}); }}",
+                            group = group.ident,
+                            name = name,
+                            symbol = symbol,
+                            param = binding.ident.trim_end_matches('_')
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if gen.has_error_context(registry) && cmd.proto.ident != "GetError" {
+            before_call.push(format!("record_recent_command(\"{}\");", symbol));
+        }
+        if gen.call_logging {
+            before_call.push(println.clone());
+        }
+        if gen.is_redundancy_checked(registry, cmd) {
+            before_call.push(format!(
+                "thread_local! {{ static PREVIOUS_ARGS: ::std::cell::Cell<Option<({types},)>> = const {{ ::std::cell::Cell::new(None) }}; }}
+                if PREVIOUS_ARGS.with(|previous| previous.replace(Some(({idents},)))) == Some(({idents},)) {{ inc_redundant_call(); }}",
+                types = typed_params.join(", "),
+                idents = idents.join(", ")
+            ));
+        }
+        if gen.last_args {
+            // the arguments are formatted on read, by `format_last_args`
+            before_call.push(format!(
+                "record_last_args({}, {}, &[{}]);",
+                index,
+                last_args_offset,
+                idents
+                    .iter()
+                    .zip(typed_params.iter())
+                    .map(|(name, ty)| if ty.contains("GLDEBUGPROC") {
+                        "0".to_string()
+                    } else {
+                        format!("LastArg::pack({})", name)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            last_args_offset += idents.len();
+        }
+
+        let call = format!(
+            "unsafe {{ __gl_imports::mem::transmute::<_, extern \"system\" fn({typed_params}) -> {return_suffix}>\
+                    (self.{name}.f)({idents}) }}",
+            name = cmd.proto.ident,
+            typed_params = typed_params.join(", "),
+            return_suffix = cmd.proto.ty,
+            idents = idents.join(", "),
+        );
+        let call = if gen.dry_run {
+            format!("if dry_run() {{ MockValue::mock_value(0) }} else {{ {} }}", call)
+        } else {
+            call
+        };
+        let bare_call = call.clone();
+        if gen.command_toggles {
+            before_call.insert(0, format!("if !command_enabled({}) {{ return {}; }}", index, bare_call));
+        }
+        let call = if cmd.proto.ident == "GetError" && gen.has_error_passthrough(registry) {
+            format!(
+                "match take_pending_error() {{ Some(error) => error as {return_suffix}, None => {call} }}",
+                return_suffix = cmd.proto.ty,
+                call = call
+            )
+        } else {
+            call
+        };
+        let call = if gen.is_cpu_timed(registry, cmd) {
+            format!(
+                "let __start = ::std::time::Instant::now();
+                let r = {call};
+                {add_time};",
+                call = call,
+                add_time = if gen.per_command_counts {
+                    format!("add_command_time({}, __start)", index)
+                } else {
+                    "add_time(__start)".to_string()
+                }
+            )
+        } else {
+            format!("let r = {call};", call = call)
+        };
+
+        let call = if gen.is_gpu_fence_timed(registry, cmd) {
+            format!("{} self.insert_timing_fence({});", call, index)
+        } else {
+            call
+        };
+        let call = match gen.gen_profiling_condition() {
+            Some(condition) => format!(
+                "let r = if {condition} {{ {call} r }} else {{ {bare_call} }};",
+                condition = condition,
+                call = call,
+                bare_call = bare_call
+            ),
+            None => call,
+        };
+
+        let print_err = if !gen.checks_each_call() {
+            format!("{};", inc_call)
+        } else if cmd.proto.ident != "GetError" && has_get_error(registry) {
+            let report = format!(
+                r#"report_error("{}", r, format_args!("{}" {}))"#,
+                symbol, call_format, call_args
+            );
+            format!(
+                r#"match unsafe {{ __gl_imports::mem::transmute::<_, extern "system" fn() -> u32>
+                    ({get_error})() }} {{ {no_error} => {inc_call}, r => {{
+                        {set_pending}
+                        {report}
+                    }} }}"#,
+                get_error = gen.gen_get_error_ptr(registry),
+                no_error = no_error,
+                inc_call = inc_call,
+                set_pending = if gen.has_error_passthrough(registry) { "set_pending_error(r);" } else { "" },
+                report = if inc_err.is_empty() {
+                    format!("{};", report)
+                } else {
+                    format!("if {} {{ {} }}", report, inc_err.trim())
+                }
+            )
+        } else if !has_get_error(registry) {
+            format!("{};", inc_call)
+        } else {
+            String::new()
+        };
+        let print_err = if gen.dry_run && gen.checks_each_call() && !print_err.is_empty() {
+            format!("if dry_run() {{ {}; }} else {{ {} }}", inc_call, print_err)
+        } else {
+            print_err
+        };
+
+        let check_return = match gen.return_check(registry, cmd) {
+            Some(predicate) => {
+                let log = if gen.stack_error_buffer > 0 {
+                    format!(
+                        r#"log_error(format_args!("{}\n{} ^ failure returned: {{:?}}" {}, r));"#,
+                        call_format, log_prefix, call_args
+                    )
+                } else {
+                    format!(
+                        r#"{} println!("{} ^ failure returned: {{:?}}", r);"#,
+                        println, log_prefix
+                    )
+                };
+                format!(
+                    "if {dry_run}({predicate})(r) {{ inc_return_err(); {log} }}",
+                    dry_run = if gen.dry_run { "!dry_run() && " } else { "" },
+                    predicate = predicate,
+                    log = log
+                )
+            }
+            None => String::new(),
+        };
+
+        let body = format!(
+            "{sample}{before_call}
+                {call}
+                {print_err}
+                {check_return}
+                r",
+            sample = if gen.sampling > 1 { "let __sampled = sample_call(); " } else { "" },
+            before_call = gen.gen_profiling_gate(before_call.join(" ")),
+            call = call,
+            print_err = gen.gen_profiling_gate(print_err),
+            check_return = gen.gen_profiling_gate(check_return)
+        );
+        let params = gen_parameters(cmd, true, true).join(", ");
+        let body = if gen.named_trampolines {
+            writeln!(
+                dest,
+                "#[allow(non_snake_case, unused_variables, dead_code)]
+            #[inline(never)] unsafe fn __call_{symbol}(&self, {params}) -> {return_suffix} {{ \
+                {body}
+            }}",
+                symbol = symbol,
+                params = params,
+                return_suffix = cmd.proto.ty,
+                body = body
+            )?;
+            format!("unsafe {{ self.__call_{}({}) }}", symbol, idents.join(", "))
+        } else {
+            body
+        };
+
+        if gen.doc_links {
+            if let Some(url) = gen_doc_link(registry.api, &symbol) {
+                writeln!(dest, "/// See <{}>", url)?;
+            }
+        }
+        for attr in &gen.wrapper_attributes {
+            writeln!(dest, "{}", attr)?;
+        }
+        writeln!(dest,
+                      "#[allow(non_snake_case, unused_variables, dead_code)]
+            #[inline] pub unsafe fn {name}(&self, {params}) -> {return_suffix} {{ \
+                {body}
+            }}",
+                      name = cmd.proto.ident,
+                      params = params,
+                      return_suffix = cmd.proto.ty,
+                      body = body)?
+    }
+
+    if gen.instance_counters {
+        write_instance_counter_fns(dest)?;
+    }
+    if gen.has_gpu_debug_groups(registry) {
+        write_debug_group_fns(gen, dest)?;
+    }
+    if gen.has_object_labels(registry) {
+        write_object_label_fns(dest)?;
+    }
+    if gen.has_gl_info(registry) {
+        write_gl_info_fns(gen, dest)?;
+    }
+    if gen.has_usability_probe(registry) {
+        write_usability_probe_fns(gen, dest)?;
+    }
+    if gen.self_benchmark && has_get_error(registry) {
+        write_self_benchmark_fns(gen, registry, dest)?;
+    }
+
+    writeln!(dest, "}}")?;
+    if gen.self_benchmark && has_get_error(registry) {
+        write_self_benchmark_struct(gen, dest)?;
+    }
+    if gen.has_gpu_debug_groups(registry) {
+        write_debug_group_scope(gen, registry, dest)?;
+    }
+    if !gen.struct_generator_compat {
+        writeln!(
+            dest,
+            "impl Default for {api} {{
+            /// Returns the `unloaded` bindings.
+            fn default() -> {api} {{
+                {api}::unloaded()
+            }}
+        }}",
+            api = generators::gen_struct_name(registry.api)
+        )?;
+    }
+    if gen.instance_counters {
+        writeln!(
+            dest,
+            "// SAFETY: the function pointers are only read after loading, and the loader returns
+            // pointers which can be called from any thread; the counters are atomics.
+            unsafe impl Sync for {api} {{}}",
+            api = generators::gen_struct_name(registry.api)
+        )?;
+    }
+    if gen.is_send() {
+        writeln!(
+            dest,
+            "unsafe impl __gl_imports::Send for {api} {{}}",
+            api = generators::gen_struct_name(registry.api)
+        )?;
+    }
+
+    if has_get_error(registry) {
+        write_assert_macro(dest)?;
+    }
+
+    for path in &gen.impl_traits {
+        write_trait_impl(path, generators::gen_struct_name(registry.api), registry, dest)?;
+    }
+
+    Ok(())
+}
+
+/// Creates the implementation of the trait at `path` for the struct `api`, which forwards to the
+/// generated methods.
+pub(crate) fn write_trait_impl<W>(path: &str, api: &str, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(dest, "impl {} for {} {{", path, api)?;
+    for cmd in &registry.cmds {
+        writeln!(
+            dest,
+            "#[allow(non_snake_case)]
+            #[inline] unsafe fn {name}(&self, {params}) -> {return_suffix} {{
+                unsafe {{ {api}::{name}(self, {idents}) }}
+            }}",
+            name = cmd.proto.ident,
+            params = gen_parameters(cmd, true, true).join(", "),
+            return_suffix = cmd.proto.ty,
+            api = api,
+            idents = gen_parameters(cmd, true, false).join(", ")
+        )?;
+    }
+    writeln!(dest, "}}")
+}
+
+/// Creates the global instance set with `with_global_instance`, and the free functions which call
+/// its methods.
+pub(crate) fn write_global_instance<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let api = generators::gen_struct_name(registry.api);
+    let names: Vec<String> = registry
+        .cmds
+        .iter()
+        .map(|cmd| if gen.global_original_names {
+            cmd.proto.ident.clone()
+        } else {
+            gen_snake_case(&cmd.proto.ident)
+        })
+        .collect();
+    let mut commands_by_name = ::std::collections::BTreeMap::new();
+    for (name, cmd) in names.iter().zip(&registry.cmds) {
+        if let Some(other) = commands_by_name.insert(name, &cmd.proto.ident) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "`with_global_instance` names the free functions of `{}` and `{}` both `{}`: use \
+                     `with_global_original_names(true)`, or leave one out with `with_command_allowlist`",
+                    generators::gen_symbol_name(registry.api, other),
+                    generators::gen_symbol_name(registry.api, &cmd.proto.ident),
+                    name
+                ),
+            ));
+        }
+    }
+
+    writeln!(
+        dest,
+        "static GLOBAL: ::std::sync::OnceLock<{api}> = ::std::sync::OnceLock::new();
+
+        /// Loads the functions into the global instance used by `get()` and the free functions.
+        /// Only the first call loads them; the later calls are ignored.
+        #[allow(dead_code)]
+        pub fn init<F>(loadfn: F) where F: FnMut(&'static str) -> *const __gl_imports::raw::c_void {{
+            GLOBAL.get_or_init(|| {api}::load_with(loadfn));
+        }}
+
+        /// Returns the global instance loaded by `init`.
+        ///
+        /// Panics if `init` has not been called.
+        #[allow(dead_code)]
+        pub fn get() -> &'static {api} {{
+            GLOBAL.get().expect(\"the global instance is used before `init`\")
+        }}",
+        api = api
+    )?;
+    if gen.global_original_names {
+        writeln!(
+            dest,
+            "/// Loads the functions into the global instance, like `init`.
+            #[allow(dead_code)]
+            pub fn load_with<F>(loadfn: F) where F: FnMut(&'static str) -> *const __gl_imports::raw::c_void {{
+                init(loadfn)
+            }}"
+        )?;
+    }
+    if !gen.instance_counters {
+        // the `Sync` impl of the instance counters covers this case
+        writeln!(
+            dest,
+            "// SAFETY: the function pointers are only read after loading, and the loader returns
+            // pointers which can be called from any thread.
+            unsafe impl Sync for {} {{}}",
+            api
+        )?;
+    }
+
+    for (name, cmd) in names.iter().zip(&registry.cmds) {
+        writeln!(
+            dest,
+            "#[allow(non_snake_case, dead_code)]
+            #[inline] pub unsafe fn {name}({params}) -> {return_suffix} {{ \
+                unsafe {{ get().{ident}({idents}) }}
+            }}",
+            name = name,
+            ident = cmd.proto.ident,
+            params = gen_parameters(cmd, true, true).join(", "),
+            return_suffix = cmd.proto.ty,
+            idents = gen_parameters(cmd, true, false).join(", ")
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates the struct set with `with_bare_struct`, which calls the same function pointers as the
+/// profiling struct without profiling the calls.
+pub(crate) fn write_bare_struct<W>(gen: &ProfilingStructGenerator, name: &str, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let api = generators::gen_struct_name(registry.api);
+    writeln!(
+        dest,
+        "/// The same bindings as `{api}`, without profiling.{send_doc}{must_use}
+        #[allow(non_camel_case_types, non_snake_case, dead_code)]
+        #[derive(Clone)]
+        pub struct {name} {{",
+        api = api,
+        name = name,
+        send_doc = if gen.is_send() { format!("\n///\n{}", SEND_DOC) } else { String::new() },
+        must_use = gen.must_use_attr()
+    )?;
+    for cmd in &registry.cmds {
+        writeln!(dest, "pub {name}: FnPtr,", name = cmd.proto.ident)?;
+    }
+    writeln!(
+        dest,
+        "}}
+        impl From<{api}> for {name} {{
+            fn from(gl: {api}) -> {name} {{
+                {name} {{",
+        api = api,
+        name = name
+    )?;
+    for cmd in &registry.cmds {
+        writeln!(dest, "{name}: gl.{name},", name = cmd.proto.ident)?;
+    }
+    writeln!(
+        dest,
+        "}}
+            }}
+        }}
+        impl {name} {{
+            /// Loads the functions in the same way as `{api}::load_with`.
+            #[allow(dead_code)]
+            pub fn load_with<F>(loadfn: F) -> {name} where F: FnMut(&'static str) -> *const __gl_imports::raw::c_void {{
+                {name}::from({api}::load_with(loadfn))
+            }}",
+        api = api,
+        name = name
+    )?;
+    for cmd in &registry.cmds {
+        let typed_params = gen_parameters(cmd, false, true);
+        for attr in &gen.wrapper_attributes {
+            writeln!(dest, "{}", attr)?;
+        }
+        writeln!(
+            dest,
+            "#[allow(non_snake_case, unused_variables, dead_code)]
+            #[inline] pub unsafe fn {name}(&self, {params}) -> {return_suffix} {{ \
+                unsafe {{ __gl_imports::mem::transmute::<_, extern \"system\" fn({typed_params}) -> {return_suffix}>\
+                    (self.{name}.f)({idents}) }}
+            }}",
+            name = cmd.proto.ident,
+            params = gen_parameters(cmd, true, true).join(", "),
+            typed_params = typed_params.join(", "),
+            return_suffix = cmd.proto.ty,
+            idents = gen_parameters(cmd, true, false).join(", ")
+        )?;
+    }
+    writeln!(dest, "}}")?;
+    if gen.is_send() {
+        writeln!(dest, "unsafe impl __gl_imports::Send for {} {{}}", name)?;
+    }
+    for path in &gen.impl_traits {
+        write_trait_impl(path, name, registry, dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_generator::{Api, Fallbacks, Profile, Registry};
+    use std::io;
+
+    use crate::builder::ProfilingStructGenerator;
+
+    /// Returns the GL 1.0 registry with `glClear_color`, whose snake case name is the one of
+    /// `glClearColor`.
+    fn colliding_registry() -> Registry {
+        let mut registry = Registry::new(Api::Gl, (1, 0), Profile::Core, Fallbacks::None, []);
+        let mut cmd = registry.cmds.iter().find(|cmd| cmd.proto.ident == "ClearColor").unwrap().clone();
+        cmd.proto.ident = "Clear_color".to_string();
+        registry.cmds.insert(cmd);
+        registry
+    }
+
+    #[test]
+    fn global_instance_rejects_the_colliding_names() {
+        let gen = ProfilingStructGenerator::new().with_global_instance(true);
+        let error = colliding_registry().write_bindings(gen, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(
+            error.to_string().contains("`glClearColor` and `glClear_color` both `clear_color`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn global_instance_keeps_the_original_names() {
+        let gen = ProfilingStructGenerator::new().with_global_original_names(true);
+        let mut dest = Vec::new();
+        colliding_registry().write_bindings(gen, &mut dest).unwrap();
+        let code = String::from_utf8(dest).unwrap();
+        assert!(code.contains("pub unsafe fn ClearColor("));
+        assert!(code.contains("pub unsafe fn Clear_color("));
+    }
+
+    #[test]
+    fn global_instance_forwards_in_snake_case() {
+        let registry = Registry::new(Api::Gl, (1, 0), Profile::Core, Fallbacks::None, []);
+        let gen = ProfilingStructGenerator::new().with_global_instance(true);
+        let mut dest = Vec::new();
+        registry.write_bindings(gen, &mut dest).unwrap();
+        let code = String::from_utf8(dest).unwrap();
+        assert!(code.contains("pub unsafe fn clear_color("));
+        assert!(code.contains("// SAFETY:"));
+    }
+}
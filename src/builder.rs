@@ -0,0 +1,1359 @@
+//! The generator struct, its builder methods and the checks of the configuration.
+
+use gl_generator::{Api, Cmd, Profile, Registry};
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::builtins::{BUILTIN_COMMAND_CATEGORIES, BUILTIN_COMMAND_VERSIONS, BUILTIN_ERROR_HINTS, Version};
+use crate::util::{enum_group, gen_profile_name, has_get_error, is_identifier, matches_any};
+
+#[derive(Clone, Debug)]
+pub struct ProfilingStructGenerator {
+    pub(crate) error_checking: bool,
+    pub(crate) call_logging: bool,
+    pub(crate) per_command_counts: bool,
+    pub(crate) cpu_timing: bool,
+    pub(crate) cpu_timing_filter: Option<Vec<String>>,
+    pub(crate) error_buffer_size: usize,
+    pub(crate) trace_capacity: usize,
+    pub(crate) frame_history: usize,
+    pub(crate) last_args: bool,
+    pub(crate) redundant_call_checks: Vec<String>,
+    pub(crate) command_toggles: bool,
+    pub(crate) gpu_debug_groups: bool,
+    pub(crate) object_labels: bool,
+    pub(crate) cached_get_error: bool,
+    pub(crate) repr_c: bool,
+    pub(crate) profiler_prefix: Option<String>,
+    pub(crate) doc_links: bool,
+    pub(crate) resolved_symbols: bool,
+    pub(crate) compact_fnptr: bool,
+    pub(crate) stack_error_buffer: usize,
+    pub(crate) return_checks: bool,
+    pub(crate) return_check_overrides: Vec<(String, String)>,
+    pub(crate) error_hints: Vec<(String, String)>,
+    pub(crate) api_version: Option<((u8, u8), Profile)>,
+    pub(crate) api_in_logs: bool,
+    pub(crate) types: bool,
+    pub(crate) types_path: Option<String>,
+    pub(crate) command_allowlist: Option<Vec<String>>,
+    pub(crate) panic_on_error: bool,
+    pub(crate) thread_local_errors: bool,
+    pub(crate) module: Option<String>,
+    pub(crate) single_thread: bool,
+    pub(crate) assert_send: bool,
+    pub(crate) mock: bool,
+    pub(crate) gpu_fence_timing: Vec<String>,
+    pub(crate) serde: bool,
+    pub(crate) error_passthrough: bool,
+    pub(crate) profiling_const: Option<bool>,
+    pub(crate) impl_traits: Vec<String>,
+    pub(crate) bare_struct: Option<String>,
+    pub(crate) instance_counters: bool,
+    pub(crate) debug_messages: bool,
+    pub(crate) enum_names: bool,
+    pub(crate) self_benchmark: bool,
+    pub(crate) struct_generator_compat: bool,
+    pub(crate) abort_on_oom: bool,
+    pub(crate) error_context_depth: usize,
+    pub(crate) count_hooks: bool,
+    pub(crate) wrapper_attributes: Vec<String>,
+    pub(crate) category_counts: bool,
+    pub(crate) command_categories: Vec<(String, String)>,
+    pub(crate) enum_validation: bool,
+    pub(crate) frame_auto_reset: bool,
+    pub(crate) global_instance: bool,
+    pub(crate) global_original_names: bool,
+    pub(crate) signal_dump: bool,
+    pub(crate) windowed_stats: bool,
+    pub(crate) named_trampolines: bool,
+    pub(crate) sampling: usize,
+    pub(crate) gl_info: bool,
+    pub(crate) batched_errors: bool,
+    pub(crate) must_use: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) usability_probe: bool,
+}
+
+/// The generator with the default configuration, the same as `ProfilingStructGenerator::new()`.
+///
+/// `ProfilingStructGenerator` used to be a unit struct, so this keeps the build scripts which pass
+/// `ProfilingStructGenerator` to `Registry::write_bindings` compiling.
+#[allow(non_upper_case_globals)]
+pub const ProfilingStructGenerator: ProfilingStructGenerator = ProfilingStructGenerator::new();
+
+impl Default for ProfilingStructGenerator {
+    fn default() -> ProfilingStructGenerator {
+        ProfilingStructGenerator::new()
+    }
+}
+
+impl ProfilingStructGenerator {
+    /// Creates a generator with the default configuration: errors are checked after every call
+    /// and only the call and error counters are maintained.
+    pub const fn new() -> ProfilingStructGenerator {
+        ProfilingStructGenerator {
+            error_checking: true,
+            call_logging: false,
+            per_command_counts: false,
+            cpu_timing: false,
+            cpu_timing_filter: None,
+            error_buffer_size: 0,
+            trace_capacity: 0,
+            frame_history: 0,
+            last_args: false,
+            redundant_call_checks: Vec::new(),
+            command_toggles: false,
+            gpu_debug_groups: false,
+            object_labels: false,
+            cached_get_error: false,
+            repr_c: false,
+            profiler_prefix: None,
+            doc_links: false,
+            resolved_symbols: false,
+            compact_fnptr: false,
+            stack_error_buffer: 0,
+            return_checks: false,
+            return_check_overrides: Vec::new(),
+            error_hints: Vec::new(),
+            api_version: None,
+            api_in_logs: false,
+            types: true,
+            types_path: None,
+            command_allowlist: None,
+            panic_on_error: false,
+            thread_local_errors: false,
+            module: None,
+            single_thread: false,
+            assert_send: true,
+            mock: false,
+            gpu_fence_timing: Vec::new(),
+            serde: false,
+            error_passthrough: false,
+            profiling_const: None,
+            impl_traits: Vec::new(),
+            bare_struct: None,
+            instance_counters: false,
+            debug_messages: false,
+            enum_names: false,
+            self_benchmark: false,
+            struct_generator_compat: false,
+            abort_on_oom: false,
+            error_context_depth: 0,
+            count_hooks: false,
+            wrapper_attributes: Vec::new(),
+            category_counts: false,
+            command_categories: Vec::new(),
+            enum_validation: false,
+            frame_auto_reset: false,
+            global_instance: false,
+            global_original_names: false,
+            signal_dump: false,
+            windowed_stats: false,
+            named_trampolines: false,
+            sampling: 1,
+            gl_info: false,
+            batched_errors: false,
+            must_use: false,
+            dry_run: false,
+            usability_probe: false,
+        }
+    }
+
+    /// Creates a generator with the lightest instrumentation: every call is counted, but errors
+    /// are not checked and nothing is timed or logged.
+    pub fn counts_only() -> ProfilingStructGenerator {
+        ProfilingStructGenerator::new()
+            .with_error_checking(false)
+            .with_cpu_timing(false)
+    }
+
+    /// Creates a generator with the heaviest instrumentation, for maximum visibility during
+    /// bring-up: every call is logged, errors are checked, and the calls, errors and CPU time are
+    /// also tracked per command.
+    pub fn full_debug() -> ProfilingStructGenerator {
+        ProfilingStructGenerator::new()
+            .with_call_logging(true)
+            .with_error_checking(true)
+            .with_per_command_counts(true)
+            .with_cpu_timing(true)
+    }
+
+    /// Calls `glGetError` after every command to count and log the errors (enabled by default).
+    ///
+    /// This consumes the error flag, so the application's own `glGetError` calls no longer see
+    /// the errors. The last consumed error is available from the generated
+    /// `profiler_take_last_error()`.
+    ///
+    /// For EGL, `eglGetError` is used instead. GLX and WGL have no such command, so their calls are
+    /// only counted, which `load_with` logs once. When disabled, every call is only counted.
+    pub fn with_error_checking(mut self, enabled: bool) -> Self {
+        self.error_checking = enabled;
+        self
+    }
+
+    /// Replaces the `glGetError` call after every command with a `profiler_flush_errors(&self)`
+    /// method, which drains, counts and logs the pending errors, and returns how many it found.
+    ///
+    /// The calls are then only counted, which saves a driver round-trip per call, and the errors
+    /// are only known to have happened since the previous flush, e.g. in the last frame. The
+    /// errors found by a flush have no command, so they are counted, logged and passed to the
+    /// error hook of `with_count_hooks`, but not kept as the first, last or recent errors.
+    ///
+    /// This takes precedence over `with_error_checking`, which keeps its value.
+    pub fn with_batched_errors(mut self, enabled: bool) -> Self {
+        self.batched_errors = enabled;
+        self
+    }
+
+    /// Returns `true` if `glGetError` is called after every command, which
+    /// `with_batched_errors` replaces with the flushes.
+    pub(crate) fn checks_each_call(&self) -> bool {
+        self.error_checking && !self.batched_errors
+    }
+
+    /// Logs every call with its arguments, like `DebugStructGenerator` does.
+    pub fn with_call_logging(mut self, enabled: bool) -> Self {
+        self.call_logging = enabled;
+        self
+    }
+
+    /// Tracks the calls and errors (and the CPU time, if timing is enabled) of each command
+    /// separately.
+    ///
+    /// The counters are available from the generated `profiler_call_counts()`,
+    /// `profiler_call_count_for(name)`, `profiler_err_count_for(name)` and
+    /// `profiler_time_ns_for(name)`.
+    pub fn with_per_command_counts(mut self, enabled: bool) -> Self {
+        self.per_command_counts = enabled;
+        self
+    }
+
+    /// Counts the calls of each category of commands, such as `"draw"`, `"state"`, `"buffer"`,
+    /// `"texture"`, `"shader"` and `"query"`, for a coarse breakdown of the calls. The counts are
+    /// returned by the generated `profiler_category_counts()`.
+    ///
+    /// The commands are categorized by their names; the ones which are not recognized are counted
+    /// as `"other"`.
+    pub fn with_category_counts(mut self, enabled: bool) -> Self {
+        self.category_counts = enabled;
+        self
+    }
+
+    /// Puts the commands matching the patterns (as in `with_cpu_timing_filter`) in the given
+    /// categories, e.g. `("glBindVertexArray", "vertex")`, which also enables
+    /// `with_category_counts(true)`.
+    ///
+    /// These take precedence over the built-in categories, and an empty category leaves the
+    /// matching commands uncounted.
+    pub fn with_command_categories<I, P, S>(mut self, categories: I) -> Self
+        where
+            I: IntoIterator<Item = (P, S)>,
+            P: Into<String>,
+            S: Into<String>,
+    {
+        self.command_categories
+            .extend(categories.into_iter().map(|(pattern, category)| (pattern.into(), category.into())));
+        self.category_counts = true;
+        self
+    }
+
+    /// Returns the names of the categories, ordered by name, and the index of the category of
+    /// each command, if it has one.
+    pub(crate) fn categories(&self, registry: &Registry) -> (Vec<String>, Vec<Option<usize>>) {
+        let categories: Vec<_> = registry
+            .cmds
+            .iter()
+            .map(|cmd| {
+                let custom = self
+                    .command_categories
+                    .iter()
+                    .rev()
+                    .find(|&(pattern, _)| matches_any(registry, cmd, std::slice::from_ref(pattern)));
+                match custom {
+                    Some((_, category)) => category.clone(),
+                    None => BUILTIN_COMMAND_CATEGORIES
+                        .iter()
+                        .find(|&&(pattern, _)| matches_any(registry, cmd, &[pattern.to_string()]))
+                        .map_or("other", |&(_, category)| category)
+                        .to_string(),
+                }
+            })
+            .collect();
+        let mut names: Vec<_> = categories.iter().filter(|c| !c.is_empty()).cloned().collect();
+        names.sort();
+        names.dedup();
+        let indices = categories
+            .iter()
+            .map(|c| names.iter().position(|name| name == c))
+            .collect();
+        (names, indices)
+    }
+
+    /// Measures the CPU time spent inside GL commands using `std::time::Instant`.
+    ///
+    /// The accumulated time is available from the generated `profiler_time_ns()`.
+    pub fn with_cpu_timing(mut self, enabled: bool) -> Self {
+        self.cpu_timing = enabled;
+        self
+    }
+
+    /// Restricts CPU timing to the commands matching one of the `patterns`; all other commands
+    /// are only counted.
+    ///
+    /// A pattern is either a symbol name (`"glDrawArrays"`) or a command name (`"DrawArrays"`),
+    /// and a trailing `*` matches any suffix (`"glDraw*"`). Without a filter, every command is
+    /// timed.
+    pub fn with_cpu_timing_filter<I, S>(mut self, patterns: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+    {
+        self.cpu_timing_filter = Some(patterns.into_iter().map(|p| p.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Keeps the last `size` errors in a fixed-capacity buffer, available from the generated
+    /// `profiler_recent_errors()`. A size of `0` (the default) disables the buffer.
+    pub fn with_error_buffer_size(mut self, size: usize) -> Self {
+        self.error_buffer_size = size;
+        self
+    }
+
+    /// Keeps the names of the last `depth` commands called on each thread, and logs them with
+    /// every error, e.g. `recent: glUseProgram, glBindVertexArray, glDrawArrays`, to show the state
+    /// setup which led to it. A depth of `0` (the default) disables it.
+    pub fn with_error_context_depth(mut self, depth: usize) -> Self {
+        self.error_context_depth = depth;
+        self
+    }
+
+    pub(crate) fn has_error_context(&self, registry: &Registry) -> bool {
+        self.error_context_depth > 0 && self.checks_each_call() && has_get_error(registry)
+    }
+
+    /// Keeps the names of the last `capacity` called commands in a fixed-capacity buffer,
+    /// available from the generated `profiler_trace()`. A capacity of `0` (the default) disables
+    /// the trace.
+    pub fn with_trace_capacity(mut self, capacity: usize) -> Self {
+        self.trace_capacity = capacity;
+        self
+    }
+
+    /// Keeps the call counts of the last `frames` frames, as returned by `profiler_frame_end()`,
+    /// in a fixed-capacity buffer available from the generated `profiler_frame_history()`. A
+    /// size of `0` (the default) disables the history.
+    pub fn with_frame_history(mut self, frames: usize) -> Self {
+        self.frame_history = frames;
+        self
+    }
+
+    /// Counts the calls of the last second in 10 buckets of 100ms, returned by the generated
+    /// `profiler_calls_last_window()`, e.g. to show the current rate of calls in a HUD.
+    ///
+    /// Each call reads the clock to find its bucket.
+    pub fn with_windowed_stats(mut self, enabled: bool) -> Self {
+        self.windowed_stats = enabled;
+        self
+    }
+
+    /// Generates `profiler_install_signal_handler(signal)`, which makes the signal (e.g.
+    /// `SIGUSR1`) request a dump of the profiler, to inspect a long-running process.
+    ///
+    /// Printing is not allowed in a signal handler, so the handler only sets a flag, and the dump
+    /// is printed by the next `profiler_frame_end()` or `profiler_dump_if_requested()`.
+    ///
+    /// The handler is installed with the C `signal` function, so
+    /// `profiler_install_signal_handler` is generated under `#[cfg(unix)]` and does not exist on
+    /// other targets. `profiler_dump_if_requested()` is generated on every target, and returns
+    /// `false` there.
+    pub fn with_signal_dump(mut self, enabled: bool) -> Self {
+        self.signal_dump = enabled;
+        self
+    }
+
+    /// Generates `profiler_frame()`, which ends the frame like `profiler_frame_end()`, and returns
+    /// the report of the frame and resets the profiler, so that the counts always cover the last
+    /// frame, e.g. for a HUD.
+    ///
+    /// The reset is destructive: the totals since the start are lost. The history of
+    /// `with_frame_history` is kept.
+    pub fn with_frame_auto_reset(mut self, enabled: bool) -> Self {
+        self.frame_auto_reset = enabled;
+        self
+    }
+
+    /// Keeps the arguments of the last call of each command, available formatted from the
+    /// generated `profiler_last_args(name)`, to see the state which led to an error.
+    ///
+    /// The calls store the arguments as bits in a fixed buffer, without locking or allocating, and
+    /// they are only formatted when read. A read racing with a call of the same command may see
+    /// the arguments of two calls mixed. Pointers are kept as addresses and callbacks are not kept.
+    pub fn with_last_args(mut self, enabled: bool) -> Self {
+        self.last_args = enabled;
+        self
+    }
+
+    /// Counts the calls of the commands matching the patterns (as in `with_cpu_timing_filter`)
+    /// whose arguments are the same as in the previous call of the command on the same thread,
+    /// e.g. binding the texture which is already bound. The count is returned by the generated
+    /// `profiler_redundant_call_count()`.
+    ///
+    /// Meant for the commands which set state, such as `glBindTexture` or `glUseProgram`. The
+    /// commands without parameters are never counted.
+    pub fn with_redundant_call_checks<I, S>(mut self, patterns: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+    {
+        self.redundant_call_checks = patterns.into_iter().map(|p| p.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Generates `profiler_set_command_enabled(name, enabled)`, which turns the instrumentation
+    /// of a command off or on at runtime. The wrappers of the disabled commands only call the
+    /// function.
+    ///
+    /// This costs an atomic load per call.
+    pub fn with_command_toggles(mut self, enabled: bool) -> Self {
+        self.command_toggles = enabled;
+        self
+    }
+
+    /// Generates a `profiler_scope(&self, name)` method, which enters a profiler scope like the
+    /// free function, and also pushes a GL debug group of the same name until the scope is exited,
+    /// so that the scopes show up in the captures of GPU debuggers.
+    ///
+    /// Requires `glPushDebugGroup` (GL 4.3, GL ES 3.2 or `KHR_debug`), and is ignored otherwise.
+    pub fn with_gpu_debug_groups(mut self, enabled: bool) -> Self {
+        self.gpu_debug_groups = enabled;
+        self
+    }
+
+    /// Generates a `label_object(&self, identifier, name, label)` method, which names a GL object
+    /// with `glObjectLabel`, e.g. to make the buffers and textures recognizable in the captures of
+    /// GPU debuggers. The method does nothing if `glObjectLabel` is not loaded at runtime, and
+    /// is not generated at all for the APIs without it.
+    pub fn with_object_labels(mut self, enabled: bool) -> Self {
+        self.object_labels = enabled;
+        self
+    }
+
+    /// Keeps the `GetError` function pointer in a static, set by `load_with`, which the error
+    /// checks call instead of reading it from the struct.
+    ///
+    /// All the instances then check the errors with the `GetError` of the last loaded one, which
+    /// is the same function for all the contexts on most platforms. Ignored with
+    /// `with_instance_counters(true)`, where the instances are meant to stay independent.
+    pub fn with_cached_get_error(mut self, enabled: bool) -> Self {
+        self.cached_get_error = enabled;
+        self
+    }
+
+    pub(crate) fn has_cached_get_error(&self, registry: &Registry) -> bool {
+        self.cached_get_error && self.checks_each_call() && !self.instance_counters && has_get_error(registry)
+    }
+
+    /// Returns the expression of the `GetError` function pointer used by the error checks.
+    pub(crate) fn gen_get_error_ptr(&self, registry: &Registry) -> &'static str {
+        if self.has_cached_get_error(registry) {
+            "GET_ERROR_FN.load(::std::sync::atomic::Ordering::Relaxed) as *const __gl_imports::raw::c_void"
+        } else {
+            "self.GetError.f"
+        }
+    }
+
+    /// Returns the beginning and the end of the construction of the struct in `load_with` and
+    /// `mock`, which also caches the `GetError` function pointer if needed.
+    pub(crate) fn gen_construction(&self, registry: &Registry) -> (&'static str, &'static str) {
+        if self.has_cached_get_error(registry) {
+            (
+                "let loaded = ",
+                ";\nGET_ERROR_FN.store(loaded.GetError.f as usize, ::std::sync::atomic::Ordering::Relaxed);\nloaded",
+            )
+        } else {
+            ("", "")
+        }
+    }
+
+    /// Generates a `profiler_capture_gl_info(&self)` method, which reads the `GL_VENDOR`,
+    /// `GL_RENDERER` and `GL_VERSION` strings with `glGetString`, and `profiler_gl_info()`, which
+    /// returns the captured strings, e.g. to attach them to bug reports.
+    ///
+    /// The method needs a current context, so it is meant to be called once after the context
+    /// is created. Requires `glGetString`, and is ignored otherwise.
+    pub fn with_gl_info(mut self, enabled: bool) -> Self {
+        self.gl_info = enabled;
+        self
+    }
+
+    pub(crate) fn has_gl_info(&self, registry: &Registry) -> bool {
+        self.gl_info && registry.cmds.iter().any(|cmd| cmd.proto.ident == "GetString")
+    }
+
+    /// Generates a `profiler_probe_usable(&self)` method, which marks each command as usable or
+    /// not in the current context, and `profiler_command_usable(name)` and
+    /// `profiler_unusable_commands()`, which read the marks, e.g. to warn that `glDispatchCompute`
+    /// is loaded but the context is too old for compute shaders.
+    ///
+    /// This is a heuristic: a command is usable if it is loaded and, for the commands of a known
+    /// later version of the API (compute, tessellation, indirect draws, debug output, ...), if
+    /// the version reported by `glGetString(GL_VERSION)` is recent enough. The generated
+    /// `profiler_set_command_usable(name, usable)` corrects the marks, e.g. after checking an
+    /// extension.
+    ///
+    /// Like `with_gl_info`, it needs a current context and `glGetString`.
+    pub fn with_usability_probe(mut self, enabled: bool) -> Self {
+        self.usability_probe = enabled;
+        self
+    }
+
+    pub(crate) fn has_usability_probe(&self, registry: &Registry) -> bool {
+        self.usability_probe && registry.cmds.iter().any(|cmd| cmd.proto.ident == "GetString")
+    }
+
+    /// Returns the version of the API which introduced the command, if it is known to need more
+    /// than the first versions.
+    pub(crate) fn required_version(&self, registry: &Registry, cmd: &Cmd) -> Option<Version> {
+        BUILTIN_COMMAND_VERSIONS
+            .iter()
+            .find(|&&(pattern, _, _)| matches_any(registry, cmd, &[pattern.to_string()]))
+            .and_then(|&(_, gl, gles)| match registry.api {
+                Api::Gles1 | Api::Gles2 => gles,
+                _ => gl,
+            })
+    }
+
+    pub(crate) fn has_object_labels(&self, registry: &Registry) -> bool {
+        self.object_labels && registry.cmds.iter().any(|cmd| cmd.proto.ident == "ObjectLabel")
+    }
+
+    pub(crate) fn has_gpu_debug_groups(&self, registry: &Registry) -> bool {
+        self.gpu_debug_groups
+            && ["PushDebugGroup", "PopDebugGroup"]
+                .iter()
+                .all(|&name| registry.cmds.iter().any(|cmd| cmd.proto.ident == name))
+    }
+
+    /// Marks the generated struct (and the one of `with_bare_struct`) as `#[must_use]`, so the
+    /// compiler warns when the result of `load_with` is dropped without being stored.
+    pub fn with_must_use(mut self, enabled: bool) -> Self {
+        self.must_use = enabled;
+        self
+    }
+
+    /// Returns the `#[must_use]` attribute of the generated structs, if enabled.
+    pub(crate) fn must_use_attr(&self) -> &'static str {
+        if self.must_use {
+            "\n#[must_use = \"the loaded GL bindings must be stored and used\"]"
+        } else {
+            ""
+        }
+    }
+
+    /// Gives the generated struct and `FnPtr` a `#[repr(C)]` layout, so the loaded function
+    /// table can be shared across an FFI boundary.
+    ///
+    /// The struct then holds one `FnPtr` per command in the order of the generated
+    /// `command_names()`.
+    pub fn with_repr_c(mut self, enabled: bool) -> Self {
+        self.repr_c = enabled;
+        self
+    }
+
+    /// Replaces the `profiler_` prefix of the generated profiler functions, e.g. with
+    /// `"gl_prof_"` the functions become `gl_prof_reset`, `gl_prof_call_count` and so on. The
+    /// `profiler_*` methods of the struct, such as `profiler_scope(&self, name)`, are renamed too.
+    ///
+    /// This avoids collisions when several generated modules are glob-imported together.
+    pub fn with_profiler_prefix<S>(mut self, prefix: S) -> Self
+        where
+            S: Into<String>,
+    {
+        self.profiler_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Returns the name of a generated profiler function, e.g. `profiler_reset` for `"reset"`, or
+    /// `gl_prof_reset` with the prefix `"gl_prof_"`.
+    pub(crate) fn fn_name(&self, suffix: &str) -> String {
+        format!("{}{}", self.profiler_prefix.as_deref().unwrap_or("profiler_"), suffix)
+    }
+
+    /// Adds a doc comment linking to the Khronos reference page to each generated command
+    /// method, so the documentation is available on hover in the IDE.
+    ///
+    /// The link is derived from the command name, so variants which share a reference page
+    /// (such as `glUniform1f`) may point to a missing page.
+    pub fn with_doc_links(mut self, enabled: bool) -> Self {
+        self.doc_links = enabled;
+        self
+    }
+
+    /// Instruments only one call in `n` on each thread, and counts it as `n` calls, to bound the
+    /// cost of the instrumentation in very hot code. The counts and the times are then estimates,
+    /// and the errors are only checked after the instrumented calls: an error is still caught by
+    /// the next check, but may be reported for a later command. A rate of `1` (the default)
+    /// instruments every call.
+    ///
+    /// Can not be combined with `with_error_passthrough(true)`.
+    pub fn with_sampling(mut self, n: usize) -> Self {
+        self.sampling = n.max(1);
+        self
+    }
+
+    /// Returns the number of calls counted for each instrumented call.
+    pub(crate) fn call_weight(&self) -> &'static str {
+        if self.sampling > 1 { "SAMPLE_WEIGHT" } else { "1" }
+    }
+
+    /// Moves the body of each command method into a method which is never inlined, named after
+    /// the command, e.g. `__call_glDrawArrays`, so that the sampling profilers (perf, Instruments,
+    /// VTune) show the time of each command by name. This costs a call per command.
+    pub fn with_named_trampolines(mut self, enabled: bool) -> Self {
+        self.named_trampolines = enabled;
+        self
+    }
+
+    /// Adds the attribute `attr` to each generated command method, e.g.
+    /// `"#[cfg_attr(coverage_nightly, coverage(off))]"` to leave the methods out of the coverage
+    /// reports. Can be called several times to add several attributes.
+    pub fn with_wrapper_attribute<S>(mut self, attr: S) -> Self
+        where
+            S: Into<String>,
+    {
+        self.wrapper_attributes.push(attr.into());
+        self
+    }
+
+    /// Records in each `FnPtr` which symbol (the command itself or one of its fallbacks)
+    /// satisfied the load, available from the generated `resolved_symbol(&self, name)`.
+    pub fn with_resolved_symbols(mut self, enabled: bool) -> Self {
+        self.resolved_symbols = enabled;
+        self
+    }
+
+    /// Makes `FnPtr` a `#[repr(transparent)]` wrapper of the function pointer, which halves the
+    /// size of the struct. Whether a function is loaded is then found by comparing the pointer
+    /// with the one of the function which panics, so `FnPtr::is_loaded` is not a `const fn`.
+    ///
+    /// Can not be combined with `with_resolved_symbols(true)`, which stores the symbol in `FnPtr`.
+    pub fn with_compact_fnptr(mut self, enabled: bool) -> Self {
+        self.compact_fnptr = enabled;
+        self
+    }
+
+    /// Formats the error messages into a stack buffer of `size` bytes and writes each of them to
+    /// the standard output at once, so an error storm does not churn the allocator. Longer
+    /// messages are truncated to `size` bytes, followed by a line break.
+    ///
+    /// A size of `0` (the default) formats the messages with `println!`.
+    pub fn with_stack_error_buffer(mut self, size: usize) -> Self {
+        self.stack_error_buffer = size;
+        self
+    }
+
+    /// Checks the return values of the commands which report failures through them rather than
+    /// through `glGetError`, such as `glCheckFramebufferStatus` and `glClientWaitSync`.
+    ///
+    /// The failures are logged and counted separately, by the generated
+    /// `profiler_return_err_count()`.
+    pub fn with_return_checks(mut self, enabled: bool) -> Self {
+        self.return_checks = enabled;
+        self
+    }
+
+    /// Checks the return values of the commands matching `pattern` with `predicate`, the source
+    /// of a closure which returns `true` for a failure, e.g. `"|r| r == 0"`.
+    ///
+    /// This overrides the built-in check of a command and works even if
+    /// `with_return_checks(true)` is not set. The pattern syntax is the same as for
+    /// `with_cpu_timing_filter`.
+    pub fn with_return_check<P, S>(mut self, pattern: P, predicate: S) -> Self
+        where
+            P: Into<String>,
+            S: Into<String>,
+    {
+        self.return_check_overrides.push((pattern.into(), predicate.into()));
+        self
+    }
+
+    /// Adds hints to the `INVALID_OPERATION` errors of the commands matching the patterns (as in
+    /// `with_cpu_timing_filter`), logged after the error, e.g.
+    /// `("glDrawArrays", "is a valid program bound?")`.
+    ///
+    /// Some hints are built in; these take precedence over them, and an empty hint removes the
+    /// hint of the matching commands.
+    pub fn with_error_hints<I, P, S>(mut self, hints: I) -> Self
+        where
+            I: IntoIterator<Item = (P, S)>,
+            P: Into<String>,
+            S: Into<String>,
+    {
+        self.error_hints
+            .extend(hints.into_iter().map(|(pattern, hint)| (pattern.into(), hint.into())));
+        self
+    }
+
+    /// Returns the hint logged after an `INVALID_OPERATION` error of the command, if any.
+    pub(crate) fn error_hint(&self, registry: &Registry, cmd: &Cmd) -> Option<String> {
+        let custom = self
+            .error_hints
+            .iter()
+            .rev()
+            .find(|&(pattern, _)| matches_any(registry, cmd, std::slice::from_ref(pattern)));
+        let hint = match custom {
+            Some((_, hint)) => hint.clone(),
+            None => BUILTIN_ERROR_HINTS
+                .iter()
+                .find(|&&(pattern, _)| matches_any(registry, cmd, &[pattern.to_string()]))?
+                .1
+                .to_string(),
+        };
+        if hint.is_empty() { None } else { Some(hint) }
+    }
+
+    /// Records the version and profile the registry was created with, which the registry itself
+    /// does not keep. They are emitted as the `API_VERSION` and `API_PROFILE` constants, next to
+    /// the always emitted `API`.
+    pub fn with_api_version(mut self, version: (u8, u8), profile: Profile) -> Self {
+        self.api_version = Some((version, profile));
+        self
+    }
+
+    /// Includes the API (and the version and profile, if set with `with_api_version`) in the
+    /// prefix of the log messages, e.g. `[OpenGL gl 4.5 core]`, which makes error reports
+    /// gathered from different machines easier to aggregate.
+    pub fn with_api_in_logs(mut self, enabled: bool) -> Self {
+        self.api_in_logs = enabled;
+        self
+    }
+
+    /// Emits the `types` module with the type aliases (enabled by default).
+    ///
+    /// When disabled, a `types` module must be provided next to the bindings instead, or a
+    /// different path must be set with `with_types_path`. This avoids duplicate definitions when
+    /// the bindings are combined with another GL crate.
+    pub fn with_types(mut self, enabled: bool) -> Self {
+        self.types = enabled;
+        self
+    }
+
+    /// Refers to the type aliases through `path` (e.g. `crate::gl_types`) instead of the `types`
+    /// module in the enums and commands.
+    pub fn with_types_path<S>(mut self, path: S) -> Self
+        where
+            S: Into<String>,
+    {
+        self.types_path = Some(path.into());
+        self
+    }
+
+    /// Only emits the listed commands (e.g. `"glDrawArrays"`), instead of all the commands of the
+    /// registry, which cuts the generated code and sizes the per-command counters to the list.
+    /// The fallbacks of the listed commands are still tried when loading them.
+    ///
+    /// `glGetError` is kept for the error checking. The commands used by the other options, such
+    /// as `glFenceSync` for `with_gpu_fence_timing`, must be listed for these options to apply.
+    /// Writing the bindings fails if a listed command is not in the registry.
+    pub fn with_command_allowlist<I, S>(mut self, names: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+    {
+        self.command_allowlist = Some(names.into_iter().map(|n| n.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Panics on the first error, instead of logging it. The panic message shows the failed call
+    /// with its arguments, like the log line would.
+    ///
+    /// The counters are still updated before panicking.
+    pub fn with_panic_on_error(mut self, enabled: bool) -> Self {
+        self.panic_on_error = enabled;
+        self
+    }
+
+    /// Aborts the process after an `OUT_OF_MEMORY` error is logged, since the GL state is then
+    /// undefined. The generated `profiler_set_oom_hook(Some(hook))` replaces the abort with a call
+    /// of `hook(command)`, e.g. to save the state of the application before aborting.
+    ///
+    /// Only applies to GL and GL ES.
+    pub fn with_abort_on_oom(mut self, enabled: bool) -> Self {
+        self.abort_on_oom = enabled;
+        self
+    }
+
+    /// Generates `profiler_set_count_hooks(on_call, on_err)`, which registers the functions called
+    /// with every counted call and with the code of every counted error, to feed them into an
+    /// existing metrics system. The built-in counters are still updated.
+    ///
+    /// The error hook is called for the errors found after the calls, by `poll_errors_into` and
+    /// by `profiler_flush_errors`, and for the `DEBUG_TYPE_ERROR` debug messages, which have no
+    /// error code, so the hook gets the id of the message instead.
+    ///
+    /// `profiler_clear_count_hooks()` unregisters them.
+    pub fn with_count_hooks(mut self, enabled: bool) -> Self {
+        self.count_hooks = enabled;
+        self
+    }
+
+    /// Counts the errors of each thread separately, in thread-local counters.
+    ///
+    /// The generated `profiler_err_count()` then sums the counters of all threads which have
+    /// reported an error, and `profiler_err_count_this_thread()` returns the count of the calling
+    /// thread. The threads no longer contend on a shared counter, and the errors can be attributed
+    /// to the thread which caused them.
+    pub fn with_thread_local_errors(mut self, enabled: bool) -> Self {
+        self.thread_local_errors = enabled;
+        self
+    }
+
+    /// Wraps the bindings, together with the profiler counters, in a `pub mod` with the given name.
+    ///
+    /// This allows the outputs of several `write_bindings` calls, e.g. for GL and for GLX, WGL or
+    /// EGL, to be written to the same file or included in the same module without collisions.
+    pub fn with_module<S>(mut self, name: S) -> Self
+        where
+            S: Into<String>,
+    {
+        self.module = Some(name.into());
+        self
+    }
+
+    /// Promises that the bindings are used from a single thread, so that the counters can be plain
+    /// thread-local `Cell`s instead of atomics.
+    ///
+    /// The generated struct is then not `Send`, and the `profiler_*` functions only see the calls
+    /// made on the calling thread.
+    pub fn with_single_thread(mut self, enabled: bool) -> Self {
+        self.single_thread = enabled;
+        self
+    }
+
+    /// Whether to implement `Send` for the generated struct, which holds raw function pointers
+    /// and is therefore not `Send` by itself. Enabled by default.
+    ///
+    /// The implementation asserts that the pointers returned by the loader stay valid in every
+    /// thread of the process, which is the case for the usual loaders. It does not make a GL
+    /// context usable from another thread: the context still has to be made current there.
+    /// Ignored with `with_single_thread(true)`, where the struct is never `Send`.
+    pub fn with_assert_send(mut self, enabled: bool) -> Self {
+        self.assert_send = enabled;
+        self
+    }
+
+    /// Returns `true` if the generated structs implement `Send`.
+    pub(crate) fn is_send(&self) -> bool {
+        self.assert_send && !self.single_thread
+    }
+
+    /// Generates a global instance of the struct, loaded by `init(loadfn)` and returned by
+    /// `get()`, and a free function for each command, named in snake case (e.g. `draw_arrays`),
+    /// which calls the method of the global instance. This saves passing the struct around in
+    /// small programs.
+    ///
+    /// The free functions panic if `init` has not been called.
+    ///
+    /// The generation fails with an `InvalidInput` error naming the commands if two of them have
+    /// the same name in snake case. The global instance is kept in a `OnceLock`, so the
+    /// generated code needs Rust 1.70.
+    pub fn with_global_instance(mut self, enabled: bool) -> Self {
+        self.global_instance = enabled;
+        self
+    }
+
+    /// Names the free functions of the global instance like the commands, e.g. `DrawArrays`, as
+    /// the `GlobalGenerator` of the `gl` crate does, and adds its `load_with(loadfn)`, which is
+    /// the same as `init`. Also enables `with_global_instance(true)`.
+    pub fn with_global_original_names(mut self, enabled: bool) -> Self {
+        self.global_original_names = enabled;
+        self.global_instance |= enabled;
+        self
+    }
+
+    /// Generates a `mock()` constructor, which fills the struct with stubs instead of the loaded
+    /// functions. The stubs do nothing and return zero, but the calls are still counted, so the
+    /// code which uses the bindings can be tested without a GL context.
+    ///
+    /// The values returned by the stubs can be set per command with the generated
+    /// `returning(name, value)`, e.g. to exercise the error paths. They are kept per thread, since
+    /// the stubs can not tell the mocks apart: the tests running in parallel do not interfere,
+    /// but the mocks used on the same thread share them.
+    pub fn with_mock(mut self, enabled: bool) -> Self {
+        self.mock = enabled;
+        self
+    }
+
+    /// Generates `profiler_set_dry_run(enabled)`, which makes the loaded struct count the calls
+    /// without calling the GL functions, e.g. to test the sequences of commands issued by the
+    /// code without a GPU.
+    ///
+    /// In a dry run, the commands return zero, or null, so their results are meaningless, and
+    /// neither the errors nor the returned values are checked. No GL function is called at all:
+    /// the timing fences of `with_gpu_fence_timing` are neither inserted nor collected either.
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Measures the fence latency of the commands matching the patterns (as in
+    /// `with_cpu_timing_filter`), without stalling the CPU.
+    ///
+    /// A fence is inserted with `glFenceSync` after each of these commands, and the generated
+    /// `profiler_collect_fence_latencies(&self)` method polls the pending fences with a zero
+    /// timeout. It should be called once per frame. Requires `glFenceSync` (GL 3.2 or GL ES 3.0),
+    /// and is ignored otherwise.
+    ///
+    /// The fence latency is the CPU wall-clock time from the insertion of a fence until a poll sees
+    /// it signalled. It is not the GPU execution time: it includes the time the commands were
+    /// queued, and the time until the poll. It is not per context either: the pending fences of
+    /// all the contexts and instances are kept in one queue, and their latencies are added up.
+    pub fn with_gpu_fence_timing<I, S>(mut self, patterns: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+    {
+        self.gpu_fence_timing = patterns.into_iter().map(|p| p.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Makes the errors consumed by the error checking visible to the application: the first
+    /// error found on a thread is kept pending, and returned by the next `GetError` call of that
+    /// thread instead of calling the real `glGetError`.
+    ///
+    /// This way, the application's own error handling works as if the profiler was not there.
+    pub fn with_error_passthrough(mut self, enabled: bool) -> Self {
+        self.error_passthrough = enabled;
+        self
+    }
+
+    /// Keeps everything that the stock `StructGenerator` of `gl_generator` emits exactly as it
+    /// emits it, so that the bindings can replace its bindings without changing the code which
+    /// uses them. The profiler only adds items next to them.
+    ///
+    /// This enables `with_error_passthrough`, so that `GetError` still reports the errors, and
+    /// leaves out the `Default` implementation, which the using code may provide itself. The
+    /// options which change or remove the stock items, such as `with_module`, `with_types(false)`
+    /// or `with_single_thread`, are reported as errors.
+    pub fn with_struct_generator_compat(mut self, enabled: bool) -> Self {
+        self.struct_generator_compat = enabled;
+        self
+    }
+
+    /// Emits a `pub const PROFILING: bool` with the given value, and puts all the instrumentation
+    /// of the commands behind `if PROFILING { ... }`.
+    ///
+    /// With `false`, the optimizer removes the instrumentation, while the source stays the same,
+    /// which makes it easy to measure the cost of the instrumentation itself.
+    pub fn with_profiling_const(mut self, enabled: bool) -> Self {
+        self.profiling_const = Some(enabled);
+        self
+    }
+
+    /// Implements the trait at `path` (e.g. `crate::GlBackend`) for the generated struct, by
+    /// forwarding each of its methods to the method of the command with the same name.
+    ///
+    /// The trait must declare exactly one `unsafe fn` for each command, with the same signature as
+    /// the generated methods. This allows the profiling bindings to be swapped with other bindings
+    /// which implement the same trait. Can be called several times to implement several traits.
+    ///
+    /// Some types, such as `GLsync`, are distinct in each `types` module, so the bindings which
+    /// implement the same trait should share one with `with_types_path`.
+    pub fn with_impl_trait<S>(mut self, path: S) -> Self
+        where
+            S: Into<String>,
+    {
+        self.impl_traits.push(path.into());
+        self
+    }
+
+    /// Also generates a struct named `name` with the same methods, which call the GL functions
+    /// without profiling them. It shares the types, the enums and `FnPtr` with the profiling struct,
+    /// and implements the traits added with `with_impl_trait` too.
+    pub fn with_bare_struct<S>(mut self, name: S) -> Self
+        where
+            S: Into<String>,
+    {
+        self.bare_struct = Some(name.into());
+        self
+    }
+
+    /// Also counts the calls and errors in each instance of the generated struct, which then has
+    /// `call_count()`, `err_count()` and `reset_counters()` methods.
+    ///
+    /// The struct is then `Sync`, and `shared(self) -> Arc<Self>` allows the threads to share one
+    /// instance, e.g. one per GL context, and contribute to its counters.
+    pub fn with_instance_counters(mut self, enabled: bool) -> Self {
+        self.instance_counters = enabled;
+        self
+    }
+
+    /// Generates `profiler_debug_callback`, a `GL_KHR_debug` callback which logs the messages of
+    /// the driver and counts the `DEBUG_TYPE_ERROR` ones as errors, and
+    /// `profiler_set_min_severity(severity)`, which drops the less severe messages.
+    ///
+    /// Only applies to GL and GL ES.
+    pub fn with_debug_messages(mut self, enabled: bool) -> Self {
+        self.debug_messages = enabled;
+        self
+    }
+
+    pub(crate) fn has_debug_messages(&self, registry: &Registry) -> bool {
+        self.debug_messages && matches!(registry.api, Api::Gl | Api::Gles1 | Api::Gles2)
+    }
+
+    /// Logs the `GLenum` arguments by their names, e.g. `GL_ARRAY_BUFFER` instead of `34962`.
+    ///
+    /// The names are looked up in the group of the parameter in the registry, so the arguments
+    /// of the parameters without a group are still logged as numbers.
+    pub fn with_enum_names(mut self, enabled: bool) -> Self {
+        self.enum_names = enabled;
+        self
+    }
+
+    /// Checks the `GLenum` arguments against the enums of the group of their parameter before the
+    /// call, and logs the ones which are not in it, e.g. `invalid enum 0x1234 for glBindBuffer
+    /// target`, which is more precise than the `INVALID_ENUM` error of the driver.
+    ///
+    /// This is best effort: the parameters without a group are not checked, and the groups of
+    /// the registry are not always complete, so a valid enum may be logged too.
+    pub fn with_enum_validation(mut self, enabled: bool) -> Self {
+        self.enum_validation = enabled;
+        self
+    }
+
+    /// Adds a `profiler_self_benchmark(&self, iterations)` method, which measures the cost of the
+    /// profiling by calling `GetError` in a loop, through the generated method and through the
+    /// function pointer.
+    ///
+    /// Only applies to the APIs with a `GetError` command.
+    pub fn with_self_benchmark(mut self, enabled: bool) -> Self {
+        self.self_benchmark = enabled;
+        self
+    }
+
+    /// Returns the enums of each group used by the `GLenum` parameters, if the enum names are
+    /// logged or the enums validated.
+    pub(crate) fn enum_groups<'a>(&self, registry: &'a Registry) -> BTreeMap<&'a str, Vec<&'a str>> {
+        let mut groups = BTreeMap::new();
+        if !self.enum_names && !self.enum_validation {
+            return groups;
+        }
+        for binding in registry.cmds.iter().flat_map(|cmd| cmd.params.iter()) {
+            let group = match enum_group(registry, binding) {
+                Some(group) => group,
+                None => continue,
+            };
+            groups.entry(group.ident.as_str()).or_insert_with(|| {
+                group
+                    .enums
+                    .iter()
+                    .filter(|name| {
+                        registry.enums.iter().any(|enm| &enm.ident == *name && enm.ty == "GLenum")
+                    })
+                    .map(|name| name.as_str())
+                    .collect()
+            });
+        }
+        groups.retain(|_, enums| !enums.is_empty());
+        groups
+    }
+
+    /// Returns the last fields of the generated struct, or their initialization if `init` is
+    /// `true`.
+    pub(crate) fn gen_private_fields(&self, init: bool) -> &'static str {
+        match (self.instance_counters, init) {
+            (true, false) => "counters: InstanceCounters,\n_priv: ()",
+            (true, true) => "counters: InstanceCounters::new(),\n_priv: ()",
+            (false, _) => "_priv: ()",
+        }
+    }
+
+    /// Puts the instrumentation code behind `if PROFILING { ... }` if the `PROFILING` constant is
+    /// enabled, and behind the sampling decision if the calls are sampled.
+    pub(crate) fn gen_profiling_gate(&self, code: String) -> String {
+        match self.gen_profiling_condition() {
+            Some(condition) if !code.is_empty() => format!("if {} {{ {} }}", condition, code),
+            _ => code,
+        }
+    }
+
+    /// Returns the condition under which a call is instrumented, if not always.
+    pub(crate) fn gen_profiling_condition(&self) -> Option<&'static str> {
+        match (self.profiling_const.is_some(), self.sampling > 1) {
+            (false, false) => None,
+            (true, false) => Some("PROFILING"),
+            (false, true) => Some("__sampled"),
+            (true, true) => Some("PROFILING && __sampled"),
+        }
+    }
+
+    /// Returns `true` if the error passthrough is requested, whatever the registry.
+    pub(crate) fn has_error_passthrough_option(&self) -> bool {
+        (self.error_passthrough || self.struct_generator_compat) && self.checks_each_call()
+    }
+
+    /// Returns `true` if the errors found by the error checking are passed to `GetError`.
+    pub(crate) fn has_error_passthrough(&self, registry: &Registry) -> bool {
+        (self.error_passthrough || self.struct_generator_compat) && self.checks_each_call() && has_get_error(registry)
+    }
+
+    /// Derives `serde::Serialize` and `serde::Deserialize` for the generated `ProfilerReport`,
+    /// `ProfilerSnapshot` and `ScopeStats`.
+    ///
+    /// The crate which includes the bindings needs a `serde` feature which adds the `serde`
+    /// dependency with its `derive` feature, and the feature has to be enabled: the bindings
+    /// fail with a `compile_error!` which says so otherwise. The presence of the dependency
+    /// itself can not be checked with `cfg`, so a feature without it fails on the derives.
+    pub fn with_serde(mut self, enabled: bool) -> Self {
+        self.serde = enabled;
+        self
+    }
+
+    /// Returns the attribute which derives the `serde` traits for a generated struct, if enabled.
+    pub(crate) fn serde_attr(&self) -> &'static str {
+        if self.serde {
+            "\n#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]"
+        } else {
+            ""
+        }
+    }
+
+    /// Checks that the options make sense together, so that a mistake is reported by the build
+    /// script instead of as a confusing error in the generated code.
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        if self.cpu_timing_filter.is_some() && !self.cpu_timing {
+            return invalid("`with_cpu_timing_filter` requires `with_cpu_timing(true)`".to_string());
+        }
+        if self.panic_on_error && !self.checks_each_call() {
+            return invalid("`with_panic_on_error` requires `with_error_checking(true)` without `with_batched_errors`".to_string());
+        }
+        if self.repr_c && self.instance_counters {
+            return invalid(
+                "`with_repr_c` and `with_instance_counters` can not be combined: the counters \
+                 would be part of the `#[repr(C)]` layout"
+                    .to_string(),
+            );
+        }
+        if self.single_thread && self.instance_counters {
+            return invalid(
+                "`with_single_thread` and `with_instance_counters` can not be combined: the \
+                 instance counters are meant to be shared between threads"
+                    .to_string(),
+            );
+        }
+        if self.compact_fnptr && self.resolved_symbols {
+            return invalid(
+                "`with_compact_fnptr` and `with_resolved_symbols` can not be combined: the \
+                 compact `FnPtr` has no room for the symbol"
+                    .to_string(),
+            );
+        }
+        if self.single_thread && self.thread_local_errors {
+            return invalid(
+                "`with_single_thread` and `with_thread_local_errors` can not be combined: the \
+                 single-threaded counters are already thread-local"
+                    .to_string(),
+            );
+        }
+        if self.sampling > 1 && self.has_error_passthrough_option() {
+            return invalid(
+                "`with_sampling` and `with_error_passthrough` can not be combined: the errors \
+                 would only be passed to the instrumented `GetError` calls"
+                    .to_string(),
+            );
+        }
+        if self.global_instance && !self.is_send() {
+            return invalid(
+                "`with_global_instance` requires a `Send` struct: it can not be combined with \
+                 `with_single_thread` or `with_assert_send(false)`"
+                    .to_string(),
+            );
+        }
+        if self.struct_generator_compat {
+            let incompatible = [
+                (self.module.is_some(), "with_module"),
+                (!self.types, "with_types(false)"),
+                (self.types_path.is_some(), "with_types_path"),
+                (self.command_allowlist.is_some(), "with_command_allowlist"),
+                (self.single_thread, "with_single_thread"),
+                (!self.assert_send, "with_assert_send(false)"),
+            ];
+            if let Some(&(_, option)) = incompatible.iter().find(|&&(used, _)| used) {
+                return invalid(format!(
+                    "`with_struct_generator_compat` and `{}` can not be combined: the bindings would \
+                     not match the ones of `StructGenerator`",
+                    option
+                ));
+            }
+        }
+        if let Some(ref prefix) = self.profiler_prefix {
+            if !is_identifier(prefix) {
+                return invalid(format!("the profiler prefix `{}` is not a valid identifier", prefix));
+            }
+        }
+        if let Some(ref name) = self.bare_struct {
+            if !is_identifier(name) {
+                return invalid(format!("the bare struct name `{}` is not a valid identifier", name));
+            }
+        }
+        if let Some(ref module) = self.module {
+            if !is_identifier(module) {
+                return invalid(format!("the module name `{}` is not a valid identifier", module));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the prefix of the generated log messages.
+    pub(crate) fn log_prefix(&self, registry: &Registry) -> String {
+        if !self.api_in_logs {
+            return "[OpenGL]".to_string();
+        }
+        match self.api_version {
+            Some(((major, minor), profile)) => format!(
+                "[OpenGL {} {}.{} {}]",
+                registry.api,
+                major,
+                minor,
+                gen_profile_name(profile)
+            ),
+            None => format!("[OpenGL {}]", registry.api),
+        }
+    }
+
+    /// Returns the source of the predicate which checks the return value of the command, if any.
+    pub(crate) fn return_check(&self, registry: &Registry, cmd: &Cmd) -> Option<String> {
+        let custom = self
+            .return_check_overrides
+            .iter()
+            .rev()
+            .find(|&(pattern, _)| matches_any(registry, cmd, std::slice::from_ref(pattern)));
+        if let Some((_, predicate)) = custom {
+            return Some(predicate.clone());
+        }
+        if !self.return_checks {
+            return None;
+        }
+        match cmd.proto.ident.as_str() {
+            "CheckFramebufferStatus" | "CheckNamedFramebufferStatus" => {
+                Some("|r| r != FRAMEBUFFER_COMPLETE".to_string())
+            }
+            "ClientWaitSync" => Some("|r| r == WAIT_FAILED".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the return value of any command may be checked.
+    pub(crate) fn has_return_checks(&self) -> bool {
+        self.return_checks || !self.return_check_overrides.is_empty()
+    }
+
+    /// Returns `true` if the command should be wrapped in a CPU time measurement.
+    pub(crate) fn is_cpu_timed(&self, registry: &Registry, cmd: &Cmd) -> bool {
+        self.cpu_timing && match self.cpu_timing_filter {
+            Some(ref patterns) => matches_any(registry, cmd, patterns),
+            None => true,
+        }
+    }
+
+    /// Returns `true` if fence timing is enabled and the registry has the fence commands.
+    pub(crate) fn has_gpu_fence_timing(&self, registry: &Registry) -> bool {
+        !self.gpu_fence_timing.is_empty()
+            && ["FenceSync", "ClientWaitSync", "DeleteSync"]
+                .iter()
+                .all(|&name| registry.cmds.iter().any(|cmd| cmd.proto.ident == name))
+    }
+
+    /// Returns `true` if the arguments of the command are compared with the ones of its previous
+    /// call.
+    pub(crate) fn is_redundancy_checked(&self, registry: &Registry, cmd: &Cmd) -> bool {
+        !self.redundant_call_checks.is_empty()
+            && !cmd.params.is_empty()
+            // the callbacks can not be compared reliably
+            && !cmd.params.iter().any(|p| p.ty.contains("GLDEBUGPROC"))
+            && matches_any(registry, cmd, &self.redundant_call_checks)
+    }
+
+    /// Returns `true` if a fence is inserted after the command.
+    pub(crate) fn is_gpu_fence_timed(&self, registry: &Registry, cmd: &Cmd) -> bool {
+        self.has_gpu_fence_timing(registry) && matches_any(registry, cmd, &self.gpu_fence_timing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_generator::{Api, Fallbacks, Profile, Registry};
+    use std::io;
+
+    use super::ProfilingStructGenerator;
+
+    fn write(gen: ProfilingStructGenerator) -> io::Result<Vec<u8>> {
+        let registry = Registry::new(Api::Gl, (1, 0), Profile::Core, Fallbacks::None, []);
+        let mut dest = Vec::new();
+        registry.write_bindings(gen, &mut dest)?;
+        Ok(dest)
+    }
+
+    fn assert_rejected(gen: ProfilingStructGenerator, message: &str) {
+        let error = write(gen).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains(message), "`{}` does not contain `{}`", error, message);
+    }
+
+    #[test]
+    fn accepts_the_presets() {
+        assert!(write(ProfilingStructGenerator::new()).is_ok());
+        assert!(write(ProfilingStructGenerator::counts_only()).is_ok());
+        assert!(write(ProfilingStructGenerator::full_debug()).is_ok());
+    }
+
+    #[test]
+    fn rejects_the_conflicting_options() {
+        let new = ProfilingStructGenerator::new;
+        assert_rejected(
+            new().with_cpu_timing(false).with_cpu_timing_filter(["glClear"]),
+            "`with_cpu_timing_filter` requires `with_cpu_timing(true)`",
+        );
+        assert_rejected(new().with_panic_on_error(true).with_error_checking(false), "`with_panic_on_error` requires");
+        assert_rejected(new().with_panic_on_error(true).with_batched_errors(true), "without `with_batched_errors`");
+        assert_rejected(
+            new().with_repr_c(true).with_instance_counters(true),
+            "`with_repr_c` and `with_instance_counters` can not be combined",
+        );
+        assert_rejected(
+            new().with_single_thread(true).with_instance_counters(true),
+            "`with_single_thread` and `with_instance_counters` can not be combined",
+        );
+        assert_rejected(
+            new().with_compact_fnptr(true).with_resolved_symbols(true),
+            "`with_compact_fnptr` and `with_resolved_symbols` can not be combined",
+        );
+        assert_rejected(
+            new().with_single_thread(true).with_thread_local_errors(true),
+            "`with_single_thread` and `with_thread_local_errors` can not be combined",
+        );
+        assert_rejected(
+            new().with_sampling(2).with_error_passthrough(true),
+            "`with_sampling` and `with_error_passthrough` can not be combined",
+        );
+        assert_rejected(
+            new().with_global_instance(true).with_assert_send(false),
+            "`with_global_instance` requires a `Send` struct",
+        );
+        assert_rejected(
+            new().with_struct_generator_compat(true).with_module("gl"),
+            "`with_struct_generator_compat` and `with_module` can not be combined",
+        );
+    }
+
+    #[test]
+    fn rejects_the_invalid_identifiers() {
+        let new = ProfilingStructGenerator::new;
+        assert_rejected(new().with_profiler_prefix("gl-prof_"), "the profiler prefix `gl-prof_`");
+        assert_rejected(new().with_bare_struct("Bare Gl"), "the bare struct name `Bare Gl`");
+        assert_rejected(new().with_module("2d"), "the module name `2d`");
+    }
+}
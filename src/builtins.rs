@@ -0,0 +1,187 @@
+//! The built-in tables of error hints, command versions and command categories.
+
+/// The hints logged after the `INVALID_OPERATION` errors of the matching commands, unless replaced
+/// with `with_error_hints`.
+pub(crate) const BUILTIN_ERROR_HINTS: &[(&str, &str)] = &[
+    ("glDrawArrays*", "is a linked program in use, and a vertex array object bound (the core profile requires one)?"),
+    ("glDrawElements*", "is a linked program in use, and a vertex array object bound with an element buffer?"),
+    ("glDrawRangeElements*", "is a linked program in use, and a vertex array object bound with an element buffer?"),
+    ("glMultiDraw*", "is a linked program in use, and a vertex array object bound (the core profile requires one)?"),
+    ("glUniform*", "is the program of the uniform in use, and does the function match the uniform type (e.g. glUniform1i for samplers)?"),
+    ("glVertexAttribPointer", "is a vertex array object bound, and a buffer bound to ARRAY_BUFFER?"),
+    ("glVertexAttribIPointer", "is a vertex array object bound, and a buffer bound to ARRAY_BUFFER?"),
+    ("glUseProgram", "has the program been linked successfully?"),
+    ("glBindTexture", "was the texture first bound to a different target?"),
+    ("glGenerateMipmap", "is the texture complete, with a color-renderable format?"),
+    ("glBeginQuery", "is a query of the same target already active?"),
+    ("glEndQuery", "is a query of this target active?"),
+    ("glBlitFramebuffer", "do the formats and the sample counts of the read and draw framebuffers match?"),
+    ("glReadPixels", "does the format and type combination suit the read framebuffer?"),
+];
+
+/// A major and minor version of the API.
+pub(crate) type Version = (u8, u8);
+
+/// The GL and GL ES versions which introduced the commands checked by `with_usability_probe`, or
+/// `None` if the command is only available from extensions. The first matching pattern wins.
+pub(crate) const BUILTIN_COMMAND_VERSIONS: &[(&str, Option<Version>, Option<Version>)] = &[
+    ("glBindVertexArray", Some((3, 0)), Some((3, 0))),
+    ("glGenVertexArrays", Some((3, 0)), Some((3, 0))),
+    ("glDeleteVertexArrays", Some((3, 0)), Some((3, 0))),
+    ("glFenceSync", Some((3, 2)), Some((3, 0))),
+    ("glClientWaitSync", Some((3, 2)), Some((3, 0))),
+    ("glDeleteSync", Some((3, 2)), Some((3, 0))),
+    ("glQueryCounter", Some((3, 3)), None),
+    ("glGetQueryObjecti64v", Some((3, 3)), None),
+    ("glGetQueryObjectui64v", Some((3, 3)), None),
+    ("glPatchParameter*", Some((4, 0)), Some((3, 2))),
+    ("glDrawArraysIndirect", Some((4, 0)), Some((3, 1))),
+    ("glDrawElementsIndirect", Some((4, 0)), Some((3, 1))),
+    ("glGetProgramBinary", Some((4, 1)), Some((3, 0))),
+    ("glProgramBinary", Some((4, 1)), Some((3, 0))),
+    ("glTexStorage2DMultisample", Some((4, 3)), Some((3, 1))),
+    ("glTexStorage3DMultisample", Some((4, 3)), Some((3, 2))),
+    ("glTexStorage*", Some((4, 2)), Some((3, 0))),
+    ("glBindImageTexture", Some((4, 2)), Some((3, 1))),
+    ("glMemoryBarrier", Some((4, 2)), Some((3, 1))),
+    ("glDispatchCompute*", Some((4, 3)), Some((3, 1))),
+    ("glMultiDrawArraysIndirect", Some((4, 3)), None),
+    ("glMultiDrawElementsIndirect", Some((4, 3)), None),
+    ("glDebugMessage*", Some((4, 3)), Some((3, 2))),
+    ("glPushDebugGroup", Some((4, 3)), Some((3, 2))),
+    ("glPopDebugGroup", Some((4, 3)), Some((3, 2))),
+    ("glObjectLabel", Some((4, 3)), Some((3, 2))),
+    ("glGetObjectLabel", Some((4, 3)), Some((3, 2))),
+    ("glBufferStorage", Some((4, 4)), None),
+    ("glClipControl", Some((4, 5)), None),
+    ("glCreateBuffers", Some((4, 5)), None),
+    ("glCreateFramebuffers", Some((4, 5)), None),
+    ("glCreateProgramPipelines", Some((4, 5)), None),
+    ("glCreateQueries", Some((4, 5)), None),
+    ("glCreateRenderbuffers", Some((4, 5)), None),
+    ("glCreateSamplers", Some((4, 5)), None),
+    ("glCreateTextures", Some((4, 5)), None),
+    ("glCreateTransformFeedbacks", Some((4, 5)), None),
+    ("glCreateVertexArrays", Some((4, 5)), None),
+    ("glSpecializeShader", Some((4, 6)), None),
+];
+
+/// The categories of the commands counted with `with_category_counts`. The first matching
+/// pattern wins.
+pub(crate) const BUILTIN_COMMAND_CATEGORIES: &[(&str, &str)] = &[
+    ("glDrawBuffer", "state"),
+    ("glNamedFramebufferDrawBuffer", "state"),
+    ("glClearBufferData", "buffer"),
+    ("glClearBufferSubData", "buffer"),
+    ("glClearNamedBuffer*", "buffer"),
+    ("glDraw*", "draw"),
+    ("glMultiDraw*", "draw"),
+    ("glDispatchCompute*", "draw"),
+    ("glClear", "draw"),
+    ("glClearBuffer*", "draw"),
+    ("glClearNamedFramebuffer*", "draw"),
+    ("glBlit*", "draw"),
+    ("glGenQueries", "query"),
+    ("glCreateQueries", "query"),
+    ("glDeleteQueries", "query"),
+    ("glIsQuery", "query"),
+    ("glBeginQuery*", "query"),
+    ("glEndQuery*", "query"),
+    ("glGetQuery*", "query"),
+    ("glQueryCounter", "query"),
+    ("glGenBuffers", "buffer"),
+    ("glCreateBuffers", "buffer"),
+    ("glDeleteBuffers", "buffer"),
+    ("glIsBuffer", "buffer"),
+    ("glBindBuffer*", "buffer"),
+    ("glBuffer*", "buffer"),
+    ("glNamedBuffer*", "buffer"),
+    ("glMapBuffer*", "buffer"),
+    ("glMapNamedBuffer*", "buffer"),
+    ("glUnmapBuffer", "buffer"),
+    ("glUnmapNamedBuffer", "buffer"),
+    ("glFlushMapped*", "buffer"),
+    ("glCopyBufferSubData", "buffer"),
+    ("glCopyNamedBufferSubData", "buffer"),
+    ("glGetBuffer*", "buffer"),
+    ("glGetNamedBuffer*", "buffer"),
+    ("glInvalidateBuffer*", "buffer"),
+    ("glGenTextures", "texture"),
+    ("glCreateTextures", "texture"),
+    ("glDeleteTextures", "texture"),
+    ("glIsTexture", "texture"),
+    ("glActiveTexture", "texture"),
+    ("glBindTexture*", "texture"),
+    ("glBindImageTexture*", "texture"),
+    ("glTex*", "texture"),
+    ("glTexture*", "texture"),
+    ("glCompressedTex*", "texture"),
+    ("glCopyTex*", "texture"),
+    ("glGetTex*", "texture"),
+    ("glGetTexture*", "texture"),
+    ("glGetCompressedTex*", "texture"),
+    ("glGenerateMipmap", "texture"),
+    ("glGenerateTextureMipmap", "texture"),
+    ("glInvalidateTex*", "texture"),
+    ("glClearTex*", "texture"),
+    ("glGenSamplers", "texture"),
+    ("glCreateSamplers", "texture"),
+    ("glDeleteSamplers", "texture"),
+    ("glBindSampler*", "texture"),
+    ("glSampler*", "texture"),
+    ("glGetSampler*", "texture"),
+    ("glCreateShader*", "shader"),
+    ("glShader*", "shader"),
+    ("glCompileShader", "shader"),
+    ("glDeleteShader", "shader"),
+    ("glIsShader", "shader"),
+    ("glAttachShader", "shader"),
+    ("glDetachShader", "shader"),
+    ("glGetShader*", "shader"),
+    ("glCreateProgram*", "shader"),
+    ("glDeleteProgram*", "shader"),
+    ("glIsProgram*", "shader"),
+    ("glLinkProgram", "shader"),
+    ("glValidateProgram*", "shader"),
+    ("glUseProgram*", "shader"),
+    ("glProgram*", "shader"),
+    ("glGetProgram*", "shader"),
+    ("glGenProgramPipelines", "shader"),
+    ("glBindProgramPipeline", "shader"),
+    ("glUniform*", "shader"),
+    ("glGetUniform*", "shader"),
+    ("glGetActive*", "shader"),
+    ("glGetAttribLocation", "shader"),
+    ("glBindAttribLocation", "shader"),
+    ("glGetFragData*", "shader"),
+    ("glBindFragData*", "shader"),
+    ("glGetSubroutine*", "shader"),
+    ("glEnable*", "state"),
+    ("glDisable*", "state"),
+    ("glBlend*", "state"),
+    ("glDepth*", "state"),
+    ("glStencil*", "state"),
+    ("glViewport*", "state"),
+    ("glScissor*", "state"),
+    ("glCullFace", "state"),
+    ("glFrontFace", "state"),
+    ("glPolygon*", "state"),
+    ("glLineWidth", "state"),
+    ("glPointSize", "state"),
+    ("glColorMask*", "state"),
+    ("glClear*", "state"),
+    ("glBindFramebuffer", "state"),
+    ("glBindRenderbuffer", "state"),
+    ("glBindVertexArray", "state"),
+    ("glVertexAttrib*", "state"),
+    ("glReadBuffer", "state"),
+    ("glPixelStore*", "state"),
+    ("glHint", "state"),
+    ("glLogicOp", "state"),
+    ("glSampleCoverage", "state"),
+    ("glSampleMask*", "state"),
+    ("glProvokingVertex", "state"),
+    ("glPrimitiveRestartIndex", "state"),
+    ("glPatchParameter*", "state"),
+    ("glMinSampleShading", "state"),
+];
@@ -0,0 +1,635 @@
+//! Writers of the error checking, reporting and suppression helpers.
+
+use gl_generator::{Api, Registry, generators};
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+use crate::util::gen_counter_static;
+
+/// Creates the function which describes the error codes returned by `GetError`.
+///
+/// GL and GL ES report errors through `glGetError` and EGL through `eglGetError`. GLX and WGL have
+/// no such command, so their calls are only counted.
+pub(crate) fn write_error_str_helper<W>(api: Api, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        "
+/// Returns the name and the description of an error code returned by `GetError`, e.g. by the
+/// application's own error checks.
+pub const fn error_string(code: u32) -> &'static str {{
+    {}(code)
+}}",
+        gen_error_codes(api).1
+    )?;
+
+    if let Api::Egl = api {
+        return dest.write_all(
+            r##"
+const fn egl_error_to_str(error: u32) -> &'static str {
+    match error {
+        0x3000 => "SUCCESS = The last function succeeded without error.",
+        0x3001 => "NOT_INITIALIZED = EGL is not initialized, or could not be initialized, for the specified EGL display connection.",
+        0x3002 => "BAD_ACCESS = EGL cannot access a requested resource (for example a context is bound in another thread).",
+        0x3003 => "BAD_ALLOC = EGL failed to allocate resources for the requested operation.",
+        0x3004 => "BAD_ATTRIBUTE = An unrecognized attribute or attribute value was passed in the attribute list.",
+        0x3005 => "BAD_CONFIG = An EGLConfig argument does not name a valid EGL frame buffer configuration.",
+        0x3006 => "BAD_CONTEXT = An EGLContext argument does not name a valid EGL rendering context.",
+        0x3007 => "BAD_CURRENT_SURFACE = The current surface of the calling thread is a window, pixel buffer or pixmap that is no longer valid.",
+        0x3008 => "BAD_DISPLAY = An EGLDisplay argument does not name a valid EGL display connection.",
+        0x3009 => "BAD_MATCH = Arguments are inconsistent (for example, a valid context requires buffers not supplied by a valid surface).",
+        0x300A => "BAD_NATIVE_PIXMAP = A NativePixmapType argument does not refer to a valid native pixmap.",
+        0x300B => "BAD_NATIVE_WINDOW = A NativeWindowType argument does not refer to a valid native window.",
+        0x300C => "BAD_PARAMETER = One or more argument values are invalid.",
+        0x300D => "BAD_SURFACE = An EGLSurface argument does not name a valid surface configured for GL rendering.",
+        0x300E => "CONTEXT_LOST = A power management event has occurred. The application must destroy all contexts and reinitialise OpenGL ES state and objects to continue rendering.",
+        _ => "Unknown error",
+    }
+}
+    "##
+            .as_bytes(),
+        );
+    }
+
+    dest.write_all(
+        r##"
+const fn gl_error_to_str(error: u32) -> &'static str {
+    match error {
+        self::NO_ERROR => {
+            "NO_ERROR = No error has been recorded.
+                        The value of this \
+                      symbolic constant is guaranteed to be 0."
+        }
+        self::INVALID_ENUM => {
+            "INVALID_ENUM = An unacceptable value is specified for an enumerated argument.
+                        \
+                      The offending command is ignored
+                        and has no other \
+                      side effect than to set the error flag."
+        }
+        self::INVALID_VALUE => {
+            "INVALID_VALUE = A numeric argument is out of range.
+                        The offending command is ignored
+                        and has no other side effect than to set the error flag."
+        }
+        self::INVALID_OPERATION => {
+            "INVALID_OPERATION = The specified operation is not allowed in the current \
+                      state.
+                        The offending command is ignored
+                        \
+                      and has no other side effect than to set the error flag."
+        }
+        self::INVALID_FRAMEBUFFER_OPERATION => {
+            "INVALID_FRAMEBUFFER_OPERATION = The command is trying to render to or read \
+                      from the framebuffer
+                        while the currently bound \
+                      framebuffer is not framebuffer
+                        complete (i.e. the \
+                      return value from
+                        glCheckFramebufferStatus
+                        \
+                      is not GL_FRAMEBUFFER_COMPLETE).
+                        The offending \
+                      command is ignored
+                        and has no other side effect than \
+                      to set the error flag."
+        }
+        self::OUT_OF_MEMORY => {
+            "OUT_OF_MEMORY = There is not enough memory left to execute the command.
+                        The state of the GL is undefined,
+                        except for the state of the error flags,
+                        after this error is recorded."
+        }
+        _ => "Unknown error",
+    }
+}
+    "##
+        .as_bytes(),
+    )
+}
+
+/// Creates `GlError`, which names the error codes of the registry.
+pub(crate) fn write_gl_error_enum<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let variants: Vec<_> = [
+        ("InvalidEnum", "INVALID_ENUM"),
+        ("InvalidValue", "INVALID_VALUE"),
+        ("InvalidOperation", "INVALID_OPERATION"),
+        ("InvalidFramebufferOperation", "INVALID_FRAMEBUFFER_OPERATION"),
+        ("OutOfMemory", "OUT_OF_MEMORY"),
+    ]
+    .iter()
+    .filter(|&&(_, ident)| registry.enums.iter().any(|e| e.ident == ident))
+    .collect();
+
+    writeln!(
+        dest,
+        r##"
+/// An error code returned by `GetError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlError {{
+    {variants}
+    /// A code without a variant.
+    Other(u32),
+}}
+
+impl GlError {{
+    /// Returns the error of the code, or `None` for `NO_ERROR`.
+    pub const fn from_code(code: u32) -> Option<GlError> {{
+        match code {{
+            self::NO_ERROR => None,
+            {from_code}
+            code => Some(GlError::Other(code)),
+        }}
+    }}
+
+    /// Returns the code of the error.
+    pub const fn code(self) -> u32 {{
+        match self {{
+            {code}
+            GlError::Other(code) => code,
+        }}
+    }}
+}}
+
+impl ::std::fmt::Display for GlError {{
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+        f.write_str(gl_error_to_str(self.code()))
+    }}
+}}
+
+impl ::std::error::Error for GlError {{}}"##,
+        variants = variants
+            .iter()
+            .map(|&&(variant, ident)| format!("/// `{}`\n    {},", ident, variant))
+            .collect::<Vec<_>>()
+            .join("\n    "),
+        from_code = variants
+            .iter()
+            .map(|&&(variant, ident)| format!("self::{} => Some(GlError::{}),", ident, variant))
+            .collect::<Vec<_>>()
+            .join("\n            "),
+        code = variants
+            .iter()
+            .map(|&&(variant, ident)| format!("GlError::{} => self::{},", variant, ident))
+            .collect::<Vec<_>>()
+            .join("\n            ")
+    )
+}
+
+/// Creates `framebuffer_status_str`, which describes the statuses returned by
+/// `CheckFramebufferStatus`, if the registry has any.
+///
+/// The statuses of the extensions usually have the same values as the core ones, in which case
+/// the core name is used.
+pub(crate) fn write_framebuffer_status_helper<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let mut statuses: Vec<_> = registry
+        .enums
+        .iter()
+        .filter(|e| {
+            ["FRAMEBUFFER_COMPLETE", "FRAMEBUFFER_INCOMPLETE_", "FRAMEBUFFER_UNDEFINED", "FRAMEBUFFER_UNSUPPORTED"]
+                .iter()
+                .any(|prefix| e.ident.starts_with(prefix))
+        })
+        .collect();
+    if statuses.is_empty() {
+        return Ok(());
+    }
+    statuses.sort_by_key(|e| (e.value.to_uppercase(), e.ident.len(), &e.ident));
+    statuses.dedup_by(|a, b| a.value.eq_ignore_ascii_case(&b.value));
+
+    writeln!(
+        dest,
+        r##"
+/// Returns the name and the description of a status returned by `CheckFramebufferStatus`.
+pub const fn framebuffer_status_str(code: u32) -> &'static str {{
+    match code {{"##
+    )?;
+    for status in statuses {
+        let description = match status.ident.as_str() {
+            "FRAMEBUFFER_COMPLETE" => " = The framebuffer is complete.",
+            "FRAMEBUFFER_UNDEFINED" => " = The default framebuffer is bound, but does not exist.",
+            "FRAMEBUFFER_INCOMPLETE_ATTACHMENT" => " = An attachment is not complete.",
+            "FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT" => " = The framebuffer has no image attached.",
+            "FRAMEBUFFER_INCOMPLETE_DIMENSIONS" => " = The attached images do not have the same size.",
+            "FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER" => " = A draw buffer names an attachment point with no image.",
+            "FRAMEBUFFER_INCOMPLETE_READ_BUFFER" => " = The read buffer names an attachment point with no image.",
+            "FRAMEBUFFER_UNSUPPORTED" => " = The combination of the formats of the attached images is not supported.",
+            "FRAMEBUFFER_INCOMPLETE_MULTISAMPLE" => {
+                " = The attached images do not have the same number of samples or sample locations."
+            }
+            "FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS" => {
+                " = The attachments are not all layered, or are layered with different targets."
+            }
+            _ => "",
+        };
+        writeln!(dest, "        {} => \"{}{}\",", status.value, status.ident, description)?;
+    }
+    writeln!(dest, "        _ => \"Unknown status\",\n    }}\n}}")
+}
+
+/// Returns the value `GetError` returns when there is no error, and the name of the generated
+/// function which describes the error codes.
+pub(crate) fn gen_error_codes(api: Api) -> (&'static str, &'static str) {
+    match api {
+        Api::Egl => ("0x3000", "egl_error_to_str"),
+        _ => ("0", "gl_error_to_str"),
+    }
+}
+
+/// Creates the `GL_KHR_debug` callback, and the severity threshold applied to its messages.
+pub(crate) fn write_debug_message_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+{min_severity}
+
+/// Orders the `DEBUG_SEVERITY_*` values, whose codes are not in the order of their severity.
+const fn severity_rank(severity: u32) -> usize {{
+    match severity {{
+        0x9146 => 3, // DEBUG_SEVERITY_HIGH
+        0x9147 => 2, // DEBUG_SEVERITY_MEDIUM
+        0x9148 => 1, // DEBUG_SEVERITY_LOW
+        _ => 0, // DEBUG_SEVERITY_NOTIFICATION
+    }}
+}}
+
+const fn severity_name(severity: u32) -> &'static str {{
+    match severity {{
+        0x9146 => "HIGH",
+        0x9147 => "MEDIUM",
+        0x9148 => "LOW",
+        _ => "NOTIFICATION",
+    }}
+}}
+
+/// Drops the debug messages less severe than `severity`, one of the `DEBUG_SEVERITY_*` values,
+/// before they are logged or counted. All the messages pass with `DEBUG_SEVERITY_NOTIFICATION`,
+/// the default.
+pub fn {profiler_set_min_severity}(severity: u32) {{
+    MIN_SEVERITY.store(severity_rank(severity), ::std::sync::atomic::Ordering::SeqCst);
+}}
+
+/// Logs the debug messages of the driver, and counts the `DEBUG_TYPE_ERROR` ones as errors.
+///
+/// Pass it to `DebugMessageCallback`, with a null user parameter.
+pub extern "system" fn {profiler_debug_callback}(
+    _source: u32,
+    gltype: u32,
+    id: u32,
+    severity: u32,
+    length: i32,
+    message: *const ::std::os::raw::c_char,
+    _user_param: *mut ::std::os::raw::c_void,
+) {{
+    if severity_rank(severity) < MIN_SEVERITY.load(::std::sync::atomic::Ordering::SeqCst) {{
+        return;
+    }}
+    let message = if message.is_null() {{
+        ::std::borrow::Cow::Borrowed("")
+    }} else if length < 0 {{
+        unsafe {{ ::std::ffi::CStr::from_ptr(message) }}.to_string_lossy()
+    }} else {{
+        String::from_utf8_lossy(unsafe {{ ::std::slice::from_raw_parts(message as *const u8, length as usize) }})
+    }};
+    if gltype == 0x824C {{
+        // DEBUG_TYPE_ERROR, which has no error code, so the hooks get the id of the message
+        count_error(id);
+    }}
+    println!("{log_prefix} debug message {{}} ({{}}): {{}}", id, severity_name(severity), message);
+}}"##,
+        min_severity = gen_counter_static(gen, "MIN_SEVERITY", "usize", None),
+        log_prefix = gen.log_prefix(registry),
+        profiler_set_min_severity = gen.fn_name("set_min_severity"),
+        profiler_debug_callback = gen.fn_name("debug_callback"),
+    )
+}
+
+/// Creates the error of the current thread which is waiting to be returned by `GetError`.
+pub(crate) fn write_pending_error_helper<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+thread_local! {
+    static PENDING_ERROR: ::std::cell::Cell<Option<u32>> = const { ::std::cell::Cell::new(None) };
+}
+
+/// Keeps the error for the next `GetError` call, unless an earlier one is still pending, like the
+/// GL error flag does.
+fn set_pending_error(error: u32) {
+    PENDING_ERROR.with(|pending| {
+        if pending.get().is_none() {
+            pending.set(Some(error));
+        }
+    });
+}
+
+fn take_pending_error() -> Option<u32> {
+    PENDING_ERROR.with(|pending| pending.take())
+}
+    "##
+        .as_bytes(),
+    )
+}
+
+/// Creates `report_error`, which counts and logs the errors found after the calls.
+///
+/// It is kept out of the wrappers, so that the path taken without errors stays small.
+pub(crate) fn write_report_error_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let log_prefix = gen.log_prefix(registry);
+    let error_to_str = gen_error_codes(registry.api).1;
+    let mut on_error_counted = vec![
+        "count_error(error);",
+        "record_first_error(command, error);",
+        "record_last_error(command, error);",
+    ];
+    if gen.error_buffer_size > 0 {
+        on_error_counted.push("record_recent_error(command, error);");
+    }
+    // the commands with the same hint, in the order of their first command
+    let mut hints: Vec<(String, Vec<String>)> = Vec::new();
+    if registry.api != Api::Egl {
+        for cmd in &registry.cmds {
+            if let Some(hint) = gen.error_hint(registry, cmd) {
+                let symbol = generators::gen_symbol_name(registry.api, &cmd.proto.ident);
+                match hints.iter_mut().find(|(h, _)| *h == hint) {
+                    Some((_, symbols)) => symbols.push(symbol),
+                    None => hints.push((hint, vec![symbol])),
+                }
+            }
+        }
+    }
+
+    let mut on_error_logged = if gen.panic_on_error {
+        let panic = |hint: &str| {
+            format!(
+                r#"panic!("{{}}\n{} ^ GL error triggered: {{}}, {{}}{}", call, error, {}(error){});"#,
+                log_prefix,
+                if hint.is_empty() { String::new() } else { format!("\\n{} ^ hint: {{}}", log_prefix) },
+                error_to_str,
+                hint
+            )
+        };
+        if hints.is_empty() {
+            panic("")
+        } else {
+            format!(
+                "match invalid_operation_hint(command, error) {{\n            Some(hint) => {{ {} }}\n            None => {{ {} }}\n        }}",
+                panic(", hint"),
+                panic("")
+            )
+        }
+    } else if gen.stack_error_buffer > 0 {
+        // the call and the error are formatted together, to be written in one go
+        format!(
+            r#"log_error(format_args!("{}{} ^ GL error triggered: {{}}, {{}}", {}error, {}(error)));"#,
+            if gen.call_logging { "" } else { "{}\n" },
+            log_prefix,
+            if gen.call_logging { "" } else { "call, " },
+            error_to_str
+        )
+    } else {
+        format!(
+            r#"{}println!("{} ^ GL error triggered: {{}}, {{}}", error, {}(error));"#,
+            if gen.call_logging { "" } else { "println!(\"{}\", call);\n        " },
+            log_prefix,
+            error_to_str
+        )
+    };
+    if !hints.is_empty() && !gen.panic_on_error {
+        let log = if gen.stack_error_buffer > 0 {
+            format!(r#"log_error(format_args!("{} ^ hint: {{}}", hint));"#, log_prefix)
+        } else {
+            format!(r#"println!("{} ^ hint: {{}}", hint);"#, log_prefix)
+        };
+        on_error_logged.push_str(&format!(
+            "\n        if let Some(hint) = invalid_operation_hint(command, error) {{\n            {}\n        }}",
+            log
+        ));
+    }
+    if gen.has_error_context(registry) {
+        let log = if gen.stack_error_buffer > 0 && !gen.panic_on_error {
+            format!(r#"log_error(format_args!("{} ^ recent: {{}}", recent_commands()));"#, log_prefix)
+        } else {
+            format!(r#"println!("{} ^ recent: {{}}", recent_commands());"#, log_prefix)
+        };
+        // a panic ends the logging, so the commands are logged before it
+        on_error_logged = if gen.panic_on_error {
+            format!("{}
+        {}", log, on_error_logged)
+        } else {
+            format!("{}
+        {}", on_error_logged, log)
+        };
+    }
+
+    if !hints.is_empty() {
+        writeln!(
+            dest,
+            r##"
+/// Returns the hint for the `INVALID_OPERATION` errors of the command.
+fn invalid_operation_hint(command: &str, error: u32) -> Option<&'static str> {{
+    if error != 0x0502 {{
+        return None;
+    }}
+    match command {{"##
+        )?;
+        for (hint, symbols) in &hints {
+            let symbols: Vec<_> = symbols.iter().map(|s| format!("{:?}", s)).collect();
+            writeln!(dest, "        {} => Some({:?}),", symbols.join(" | "), hint)?;
+        }
+        writeln!(dest, "        _ => None,\n    }}\n}}")?;
+    }
+
+    writeln!(
+        dest,
+        r##"
+/// Counts and logs an error returned by `GetError` after the call of `command`, described by
+/// `call`. Returns whether the error was counted.
+#[cold]
+#[inline(never)]
+#[allow(unused_variables)]
+fn report_error(command: &'static str, error: u32, call: ::std::fmt::Arguments) -> bool {{
+    let counted = errors_counted();
+    if counted {{
+        {on_error_counted}
+    }}
+    if errors_logged() {{
+        {on_error_logged}
+    }}{on_oom}
+    counted
+}}"##,
+        on_error_counted = on_error_counted.join("\n        "),
+        on_error_logged = on_error_logged,
+        on_oom = if gen.abort_on_oom && registry.api != Api::Egl {
+            "
+    // OUT_OF_MEMORY
+    if error == 0x0505 {
+        out_of_memory(command);
+    }"
+        } else {
+            ""
+        }
+    )?;
+
+    if gen.abort_on_oom && registry.api != Api::Egl {
+        writeln!(
+            dest,
+            r##"
+static OOM_HOOK: ::std::sync::Mutex<Option<fn(&'static str)>> = ::std::sync::Mutex::new(None);
+
+/// Sets the function called with the command after an `OUT_OF_MEMORY` error instead of aborting
+/// the process, or restores the abort with `None`.
+pub fn {profiler_set_oom_hook}(hook: Option<fn(&'static str)>) {{
+    *OOM_HOOK.lock().unwrap_or_else(|e| e.into_inner()) = hook;
+}}
+
+fn out_of_memory(command: &'static str) {{
+    let hook = *OOM_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+    match hook {{
+        Some(hook) => hook(command),
+        None => {{
+            eprintln!("{} {{}} ran out of memory, the GL state is undefined: aborting", command);
+            ::std::process::abort();
+        }}
+    }}
+}}"##,
+            gen.log_prefix(registry),
+            profiler_set_oom_hook = gen.fn_name("set_oom_hook"),
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates the `SuppressGuard`, which suppresses the logging of errors on the current thread.
+pub(crate) fn write_suppress_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+thread_local! {{
+    static SUPPRESSED_LOGGING: ::std::cell::Cell<usize> = const {{ ::std::cell::Cell::new(0) }};
+    static SUPPRESSED_COUNTING: ::std::cell::Cell<usize> = const {{ ::std::cell::Cell::new(0) }};
+}}
+
+/// Suppresses the logging of the errors on the current thread while it is alive.
+///
+/// The errors are still checked, so they do not leak into the following calls.
+#[must_use]
+pub struct SuppressGuard {{
+    uncounted: bool,
+}}
+
+impl SuppressGuard {{
+    /// Also stops counting the errors while the guard is alive.
+    pub fn uncounted(mut self) -> SuppressGuard {{
+        if !self.uncounted {{
+            self.uncounted = true;
+            SUPPRESSED_COUNTING.with(|depth| depth.set(depth.get() + 1));
+        }}
+        self
+    }}
+}}
+
+impl Drop for SuppressGuard {{
+    fn drop(&mut self) {{
+        SUPPRESSED_LOGGING.with(|depth| depth.set(depth.get() - 1));
+        if self.uncounted {{
+            SUPPRESSED_COUNTING.with(|depth| depth.set(depth.get() - 1));
+        }}
+    }}
+}}
+
+/// Suppresses the logging of the errors on the current thread until the returned guard is
+/// dropped, e.g. while probing for optional capabilities.
+pub fn {profiler_suppress_errors}() -> SuppressGuard {{
+    SUPPRESSED_LOGGING.with(|depth| depth.set(depth.get() + 1));
+    SuppressGuard {{ uncounted: false }}
+}}
+
+fn errors_logged() -> bool {{
+    SUPPRESSED_LOGGING.with(|depth| depth.get() == 0)
+}}
+
+fn errors_counted() -> bool {{
+    SUPPRESSED_COUNTING.with(|depth| depth.get() == 0)
+}}
+    "##,
+        profiler_suppress_errors = gen.fn_name("suppress_errors"),
+    )
+}
+
+/// Creates the counter of the failures reported by return values.
+pub(crate) fn write_return_check_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(dest, "{}", gen_counter_static(gen, "RETURN_ERR_COUNT", "usize", None))?;
+    write!(
+        dest,
+        r##"
+pub fn {profiler_return_err_count}() -> usize {{
+    RETURN_ERR_COUNT.load(::std::sync::atomic::Ordering::SeqCst)
+}}
+
+fn inc_return_err() {{
+    RETURN_ERR_COUNT.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+}}
+    "##,
+        profiler_return_err_count = gen.fn_name("return_err_count"),
+    )
+}
+
+/// Creates the `log_error` function which writes an error message without allocating.
+pub(crate) fn write_stack_error_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+struct StackMessage {{
+    buf: [u8; {size} + 1],
+    len: usize,
+}}
+
+impl ::std::fmt::Write for StackMessage {{
+    fn write_str(&mut self, s: &str) -> ::std::fmt::Result {{
+        // the last byte is reserved for the line break
+        let mut n = s.len().min(self.buf.len() - 1 - self.len);
+        while !s.is_char_boundary(n) {{
+            n -= 1;
+        }}
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n == s.len() {{ Ok(()) }} else {{ Err(::std::fmt::Error) }}
+    }}
+}}
+
+fn log_error(args: ::std::fmt::Arguments) {{
+    use ::std::io::Write;
+    let mut message = StackMessage {{ buf: [0; {size} + 1], len: 0 }};
+    let _ = ::std::fmt::Write::write_fmt(&mut message, args);
+    message.buf[message.len] = b'\n';
+    let _ = ::std::io::stdout().write_all(&message.buf[..message.len + 1]);
+}}"##,
+        size = gen.stack_error_buffer
+    )
+}
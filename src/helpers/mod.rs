@@ -0,0 +1,1416 @@
+//! Writers of the profiler: the counters and the `profiler_*` functions which access them.
+
+pub(crate) mod errors;
+pub(crate) mod reports;
+pub(crate) mod timing;
+
+use gl_generator::{Api, Registry};
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+use crate::helpers::errors::{write_debug_message_helper, write_error_str_helper, write_framebuffer_status_helper, write_gl_error_enum, write_pending_error_helper, write_report_error_helper, write_return_check_helper, write_stack_error_helper, write_suppress_helper};
+use crate::helpers::reports::{write_export_helper, write_prometheus_helper, write_report_helper, write_snapshot_helper, write_table_helper};
+use crate::helpers::timing::{write_gpu_fence_helper, write_per_command_helper, write_timing_helper};
+use crate::util::{enum_group, gen_counter_static, gen_parameters, has_get_error};
+
+/// Creates all the profiler helpers. The `profiler_*` functions are named with `fn_name`, so that
+/// they follow the prefix set with `with_profiler_prefix`.
+pub(crate) fn write_profiler<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    if gen.single_thread {
+        write_local_counter_helper(dest)?;
+    }
+    write_helper(gen, registry, dest)?;
+    write_err_count_helper(gen, dest)?;
+    write_count_error_helper(gen, dest)?;
+    if has_get_error(registry) {
+        write_error_str_helper(registry.api, dest)?;
+        if registry.api != Api::Egl {
+            write_gl_error_enum(registry, dest)?;
+        }
+    }
+    write_framebuffer_status_helper(registry, dest)?;
+    if gen.checks_each_call() && has_get_error(registry) {
+        write_suppress_helper(gen, dest)?;
+        write_report_error_helper(gen, registry, dest)?;
+    }
+    if gen.has_error_passthrough(registry) {
+        write_pending_error_helper(dest)?;
+    }
+    if gen.instance_counters {
+        write_instance_counters_helper(gen, dest)?;
+    }
+    if gen.has_debug_messages(registry) {
+        write_debug_message_helper(gen, registry, dest)?;
+    }
+    if gen.cpu_timing {
+        write_timing_helper(gen, dest)?;
+    }
+    if gen.per_command_counts {
+        write_per_command_helper(gen, registry, dest)?;
+    }
+    if gen.category_counts {
+        write_category_helper(gen, registry, dest)?;
+    }
+    if gen.has_return_checks() {
+        write_return_check_helper(gen, dest)?;
+    }
+    if gen.stack_error_buffer > 0 {
+        write_stack_error_helper(gen, dest)?;
+    }
+    if gen.has_gpu_fence_timing(registry) {
+        write_gpu_fence_helper(gen, registry, dest)?;
+    }
+    if gen.last_args {
+        write_last_args_helper(gen, registry, dest)?;
+    }
+    if !gen.redundant_call_checks.is_empty() {
+        write_redundant_call_helper(gen, dest)?;
+    }
+    if gen.command_toggles {
+        write_command_toggle_helper(gen, registry, dest)?;
+    }
+    write_budget_helper(gen, registry, dest)?;
+    write_export_helper(gen, dest)?;
+    write_report_helper(gen, dest)?;
+    write_snapshot_helper(gen, dest)?;
+    write_table_helper(gen, dest)?;
+    write_prometheus_helper(gen, dest)?;
+    write_scope_helper(gen, dest)?;
+    if gen.signal_dump {
+        write_signal_dump_helper(gen, dest)?;
+    }
+    if gen.has_gl_info(registry) {
+        write_gl_info_helper(gen, dest)?;
+    }
+    if gen.dry_run {
+        write_dry_run_helper(gen, dest)?;
+    }
+    if gen.has_usability_probe(registry) {
+        write_usability_helper(gen, registry, dest)?;
+    }
+    if gen.error_buffer_size > 0 || gen.trace_capacity > 0 || gen.frame_history > 0 || gen.has_error_context(registry) {
+        write_buffers_helper(gen, registry, dest)?;
+    }
+    Ok(())
+}
+
+/// Creates the profiler counters and the `profiler_*` functions which access them.
+pub(crate) fn write_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let mut timing_resets = Vec::new();
+    if gen.cpu_timing {
+        timing_resets.push("TIME_NS.store(0, ::std::sync::atomic::Ordering::SeqCst);");
+        if gen.per_command_counts {
+            timing_resets.push("for t in COMMAND_TIME_NS.iter() { t.store(0, ::std::sync::atomic::Ordering::SeqCst); }");
+            timing_resets.push("for t in COMMAND_MIN_NS.iter() { t.store(u64::MAX, ::std::sync::atomic::Ordering::SeqCst); }");
+            timing_resets.push("for t in COMMAND_MAX_NS.iter() { t.store(0, ::std::sync::atomic::Ordering::SeqCst); }");
+        }
+    }
+    if gen.has_gpu_fence_timing(registry) {
+        timing_resets.push("for t in FENCE_LATENCY_NS.iter() { t.store(0, ::std::sync::atomic::Ordering::SeqCst); }");
+    }
+
+    let mut resets = vec![
+        "CALL_COUNT.store(0, ::std::sync::atomic::Ordering::SeqCst);",
+        if gen.thread_local_errors {
+            "for c in ERR_COUNTS_BY_THREAD.lock().unwrap_or_else(|e| e.into_inner()).iter() { c.store(0, ::std::sync::atomic::Ordering::SeqCst); }"
+        } else {
+            "ERR_COUNT.store(0, ::std::sync::atomic::Ordering::SeqCst);"
+        },
+        "*FIRST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = None;",
+        "*LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = None;",
+    ];
+    resets.extend(timing_resets.iter().cloned());
+    if gen.per_command_counts {
+        resets.push("for c in CALL_COUNTS.iter().chain(ERR_COUNTS.iter()) { c.store(0, ::std::sync::atomic::Ordering::SeqCst); }");
+    }
+    if gen.category_counts {
+        resets.push("for c in CATEGORY_COUNTS.iter() { c.store(0, ::std::sync::atomic::Ordering::SeqCst); }");
+    }
+    if gen.windowed_stats {
+        resets.push("for c in WINDOW_CALLS.iter() { c.store(0, ::std::sync::atomic::Ordering::SeqCst); }");
+    }
+    if gen.has_return_checks() {
+        resets.push("RETURN_ERR_COUNT.store(0, ::std::sync::atomic::Ordering::SeqCst);");
+    }
+    resets.push("FRAME_START_CALLS.store(0, ::std::sync::atomic::Ordering::SeqCst);");
+    resets.push("SCOPES.lock().unwrap_or_else(|e| e.into_inner()).clear();");
+    if gen.error_buffer_size > 0 {
+        resets.push("RECENT_ERRORS.lock().unwrap_or_else(|e| e.into_inner()).clear();");
+    }
+    if gen.trace_capacity > 0 {
+        resets.push("TRACE.lock().unwrap_or_else(|e| e.into_inner()).clear();");
+    }
+    if gen.frame_history > 0 {
+        resets.push("FRAME_HISTORY.lock().unwrap_or_else(|e| e.into_inner()).clear();");
+    }
+    if !gen.redundant_call_checks.is_empty() {
+        resets.push("REDUNDANT_CALLS.store(0, ::std::sync::atomic::Ordering::SeqCst);");
+    }
+    if gen.last_args {
+        resets.push("for called in LAST_ARGS_CALLED.iter() { called.store(false, ::std::sync::atomic::Ordering::Release); }");
+    }
+
+    // the counters cleared by `profiler_reset`, which `profiler_is_reset` checks
+    let mut zero_checks = vec![
+        format!("{}() == 0", gen.fn_name("call_count")),
+        format!("{}() == 0", gen.fn_name("err_count")),
+    ];
+    let all_zero = |name: &str| format!("{}.iter().all(|c| c.load(::std::sync::atomic::Ordering::SeqCst) == 0)", name);
+    if gen.per_command_counts {
+        zero_checks.push(all_zero("CALL_COUNTS"));
+        zero_checks.push(all_zero("ERR_COUNTS"));
+    }
+    if gen.category_counts {
+        zero_checks.push(all_zero("CATEGORY_COUNTS"));
+    }
+    if gen.windowed_stats {
+        zero_checks.push(all_zero("WINDOW_CALLS"));
+    }
+    if gen.cpu_timing {
+        zero_checks.push("TIME_NS.load(::std::sync::atomic::Ordering::SeqCst) == 0".to_string());
+        if gen.per_command_counts {
+            zero_checks.push(all_zero("COMMAND_TIME_NS"));
+        }
+    }
+    if gen.has_gpu_fence_timing(registry) {
+        zero_checks.push(all_zero("FENCE_LATENCY_NS"));
+    }
+    if gen.has_return_checks() {
+        zero_checks.push("RETURN_ERR_COUNT.load(::std::sync::atomic::Ordering::SeqCst) == 0".to_string());
+    }
+    if !gen.redundant_call_checks.is_empty() {
+        zero_checks.push("REDUNDANT_CALLS.load(::std::sync::atomic::Ordering::SeqCst) == 0".to_string());
+    }
+
+    if let Some(enabled) = gen.profiling_const {
+        writeln!(
+            dest,
+            "
+/// Whether the commands are instrumented. The instrumentation is removed by the optimizer when
+/// `false`.
+pub const PROFILING: bool = {};",
+            enabled
+        )?;
+    }
+
+    writeln!(
+        dest,
+        r##"
+{call_count}
+static FIRST_ERROR: ::std::sync::Mutex<Option<(&'static str, u32)>> = ::std::sync::Mutex::new(None);
+static LAST_ERROR: ::std::sync::Mutex<Option<(&'static str, u32)>> = ::std::sync::Mutex::new(None);
+
+pub fn {profiler_reset}() {{
+    {resets}
+}}
+
+/// Returns whether all the counters, and the measured times, are zero, as after
+/// `{profiler_reset}()`, e.g. to check the isolation of the tests.
+pub fn {profiler_is_reset}() -> bool {{
+    {zero_checks}
+}}"##,
+        call_count = gen_counter_static(gen, "CALL_COUNT", "usize", None),
+        resets = resets.join("\n    "),
+        zero_checks = zero_checks.join("\n        && "),
+        profiler_reset = gen.fn_name("reset"),
+        profiler_is_reset = gen.fn_name("is_reset"),
+    )?;
+
+    if gen.frame_auto_reset {
+        // the history of the frames is kept, since it is fed by this function
+        let frame_resets: Vec<_> = resets.iter().filter(|reset| !reset.starts_with("FRAME_HISTORY")).cloned().collect();
+        writeln!(
+            dest,
+            r##"
+/// Ends the frame like `{profiler_frame_end}()`, and returns the report of the frame, after which
+/// the profiler is reset for the next one, except for the history of the frames.
+///
+/// This discards the counts since the start; the calls made by other threads between taking the
+/// report and resetting are lost.
+pub fn {profiler_frame}() -> ProfilerReport {{
+    {profiler_frame_end}();
+    let report = {profiler_report}();
+    {}
+    report
+}}"##,
+            frame_resets.join("\n    "),
+            profiler_frame_end = gen.fn_name("frame_end"),
+            profiler_frame = gen.fn_name("frame"),
+            profiler_report = gen.fn_name("report"),
+        )?;
+    }
+
+    if !timing_resets.is_empty() {
+        writeln!(
+            dest,
+            r##"
+/// Resets the measured times only, keeping the counts, e.g. to discard the warm-up frames.
+pub fn {profiler_reset_timing}() {{
+    {}
+}}"##,
+            timing_resets.join("\n    "),
+            profiler_reset_timing = gen.fn_name("reset_timing"),
+        )?;
+    }
+
+    let mut summary = vec![
+        format!(r#"format!("{{}} calls", {}())"#, gen.fn_name("call_count")),
+        format!(r#"format!("{{}} errors", {}())"#, gen.fn_name("err_count")),
+    ];
+    if gen.has_return_checks() {
+        summary.push(format!(r#"format!("{{}} failures", {}())"#, gen.fn_name("return_err_count")));
+    }
+    if gen.cpu_timing {
+        summary.push(format!(r#"format!("{{:.1}}ms", {}())"#, gen.fn_name("time_ms")));
+    }
+    if gen.has_gpu_fence_timing(registry) {
+        summary.push(format!(r#"format!("{{:.1}}ms fence latency", {}() as f64 / 1e6)"#, gen.fn_name("fence_latency_ns")));
+    }
+    writeln!(
+        dest,
+        r##"
+/// Returns the enabled metrics on one line, e.g. `{api}: 1234 calls, 2 errors, 3.1ms`.
+pub fn {profiler_summary}() -> String {{
+    format!("{api}: {{}}", [
+        {parts},
+    ].join(", "))
+}}"##,
+        api = if registry.api == Api::Egl { "EGL" } else { "GL" },
+        parts = summary.join(",\n        "),
+        profiler_summary = gen.fn_name("summary"),
+    )?;
+
+    write!(
+        dest,
+        r##"
+pub fn {profiler_call_count}() -> usize {{
+    CALL_COUNT.load(::std::sync::atomic::Ordering::SeqCst)
+}}
+
+/// Returns the same as `{profiler_call_count}`, with a `Relaxed` load, e.g. for sampling the count
+/// every frame.
+pub fn {profiler_call_count_relaxed}() -> usize {{
+    CALL_COUNT.load(::std::sync::atomic::Ordering::Relaxed)
+}}
+
+pub fn {profiler_first_error}() -> Option<(&'static str, u32)> {{
+    *FIRST_ERROR.lock().unwrap_or_else(|e| e.into_inner())
+}}
+
+#[allow(dead_code)]
+fn record_first_error(command: &'static str, error: u32) {{
+    let mut first = FIRST_ERROR.lock().unwrap_or_else(|e| e.into_inner());
+    if first.is_none() {{
+        *first = Some((command, error));
+    }}
+}}
+
+/// Returns the command and the code of the last error consumed by the error checking, and
+/// clears it, like `glGetError` would.
+pub fn {profiler_take_last_error}() -> Option<(&'static str, u32)> {{
+    LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).take()
+}}
+
+#[allow(dead_code)]
+fn record_last_error(command: &'static str, error: u32) {{
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some((command, error));
+}}
+    "##,
+        profiler_call_count = gen.fn_name("call_count"),
+        profiler_call_count_relaxed = gen.fn_name("call_count_relaxed"),
+        profiler_first_error = gen.fn_name("first_error"),
+        profiler_take_last_error = gen.fn_name("take_last_error"),
+    )?;
+
+    writeln!(
+        dest,
+        r##"
+fn inc_call() {{
+    CALL_COUNT.fetch_add({weight}, ::std::sync::atomic::Ordering::SeqCst);{inc_window_call}{run_call_hook}
+}}"##,
+        weight = gen.call_weight(),
+        inc_window_call = if gen.windowed_stats { "\n    inc_window_call();" } else { "" },
+        run_call_hook = if gen.count_hooks { "\n    run_call_hook();" } else { "" }
+    )?;
+
+    if gen.sampling > 1 {
+        writeln!(
+            dest,
+            r##"
+/// The number of calls counted for each instrumented call.
+const SAMPLE_WEIGHT: usize = {sampling};
+
+thread_local! {{
+    static CALLS_UNTIL_SAMPLE: ::std::cell::Cell<usize> = const {{ ::std::cell::Cell::new(0) }};
+}}
+
+/// Returns whether the current call is instrumented, which is one call in `SAMPLE_WEIGHT` on
+/// each thread.
+fn sample_call() -> bool {{
+    CALLS_UNTIL_SAMPLE.with(|calls| match calls.get() {{
+        0 => {{
+            calls.set(SAMPLE_WEIGHT - 1);
+            true
+        }}
+        n => {{
+            calls.set(n - 1);
+            false
+        }}
+    }})
+}}"##,
+            sampling = gen.sampling
+        )?;
+    }
+
+    if gen.windowed_stats {
+        writeln!(
+            dest,
+            r##"
+const WINDOW_BUCKETS: usize = 10;
+const WINDOW_BUCKET_MS: u64 = 100;
+
+static WINDOW_START: ::std::sync::OnceLock<::std::time::Instant> = ::std::sync::OnceLock::new();
+// the calls of each bucket, and the number of the bucket which they belong to, counted in
+// `WINDOW_BUCKET_MS` since `WINDOW_START`
+{window_calls}
+{window_bucket_ids}"##,
+            window_calls = gen_counter_static(gen, "WINDOW_CALLS", "usize", Some(10)),
+            window_bucket_ids = gen_counter_static(gen, "WINDOW_BUCKET_IDS", "u64", Some(10))
+        )?;
+        write!(
+            dest,
+            r##"
+fn current_window_bucket() -> u64 {{
+    WINDOW_START.get_or_init(::std::time::Instant::now).elapsed().as_millis() as u64 / WINDOW_BUCKET_MS
+}}
+
+/// Returns the number of calls in the last second, counted in buckets of 100ms, so the count
+/// changes in steps.
+pub fn {profiler_calls_last_window}() -> usize {{
+    let bucket = current_window_bucket();
+    (0..WINDOW_BUCKETS)
+        .filter(|&i| bucket.saturating_sub(WINDOW_BUCKET_IDS[i].load(::std::sync::atomic::Ordering::SeqCst)) < WINDOW_BUCKETS as u64)
+        .map(|i| WINDOW_CALLS[i].load(::std::sync::atomic::Ordering::SeqCst))
+        .sum()
+}}
+
+fn inc_window_call() {{
+    let bucket = current_window_bucket();
+    let i = (bucket % WINDOW_BUCKETS as u64) as usize;
+    // the first call in a new bucket clears the calls of the bucket it replaces; the calls made
+    // by other threads at the same moment may be lost
+    if WINDOW_BUCKET_IDS[i].load(::std::sync::atomic::Ordering::SeqCst) != bucket {{
+        WINDOW_BUCKET_IDS[i].store(bucket, ::std::sync::atomic::Ordering::SeqCst);
+        WINDOW_CALLS[i].store(0, ::std::sync::atomic::Ordering::SeqCst);
+    }}
+    WINDOW_CALLS[i].fetch_add({call_weight}, ::std::sync::atomic::Ordering::SeqCst);
+}}
+"##,
+            call_weight = gen.call_weight(),
+            profiler_calls_last_window = gen.fn_name("calls_last_window"),
+        )?;
+    }
+
+    if gen.count_hooks {
+        write!(
+            dest,
+            r##"
+// the hooks are read by every call, so they are kept in atomics instead of a mutex
+static CALL_HOOK: ::std::sync::atomic::AtomicPtr<()> = ::std::sync::atomic::AtomicPtr::new(::std::ptr::null_mut());
+static ERR_HOOK: ::std::sync::atomic::AtomicPtr<()> = ::std::sync::atomic::AtomicPtr::new(::std::ptr::null_mut());
+
+/// Registers the functions called with every counted call, and with the code of every counted
+/// error, in addition to the built-in counters.
+pub fn {profiler_set_count_hooks}(on_call: fn(), on_err: fn(u32)) {{
+    CALL_HOOK.store(on_call as *mut (), ::std::sync::atomic::Ordering::SeqCst);
+    ERR_HOOK.store(on_err as *mut (), ::std::sync::atomic::Ordering::SeqCst);
+}}
+
+/// Unregisters the functions registered by `{profiler_set_count_hooks}`.
+pub fn {profiler_clear_count_hooks}() {{
+    CALL_HOOK.store(::std::ptr::null_mut(), ::std::sync::atomic::Ordering::SeqCst);
+    ERR_HOOK.store(::std::ptr::null_mut(), ::std::sync::atomic::Ordering::SeqCst);
+}}
+
+fn run_call_hook() {{
+    let hook = CALL_HOOK.load(::std::sync::atomic::Ordering::Acquire);
+    if !hook.is_null() {{
+        // only `fn()` pointers are stored in `CALL_HOOK`
+        let hook = unsafe {{ ::std::mem::transmute::<*mut (), fn()>(hook) }};
+        hook();
+    }}
+}}
+
+#[allow(dead_code)]
+fn run_err_hook(error: u32) {{
+    let hook = ERR_HOOK.load(::std::sync::atomic::Ordering::Acquire);
+    if !hook.is_null() {{
+        // only `fn(u32)` pointers are stored in `ERR_HOOK`
+        let hook = unsafe {{ ::std::mem::transmute::<*mut (), fn(u32)>(hook) }};
+        hook(error);
+    }}
+}}
+"##,
+            profiler_set_count_hooks = gen.fn_name("set_count_hooks"),
+            profiler_clear_count_hooks = gen.fn_name("clear_count_hooks"),
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates the slots which keep the arguments of the last call of each command, and the function
+/// which formats them.
+pub(crate) fn write_last_args_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+/// The arguments of the last call of each command, as bits, in the order of `command_names()`.
+static LAST_ARGS: [::std::sync::atomic::AtomicU64; {total}] = {{
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: ::std::sync::atomic::AtomicU64 = ::std::sync::atomic::AtomicU64::new(0);
+    [INIT; {total}]
+}};
+
+/// Whether each command was called since the last reset.
+static LAST_ARGS_CALLED: [::std::sync::atomic::AtomicBool; {count}] = {{
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+    [INIT; {count}]
+}};
+
+/// An argument which can be kept in `LAST_ARGS`.
+trait LastArg: Copy {{
+    fn pack(self) -> u64;
+    fn unpack(bits: u64) -> Self;
+}}
+
+macro_rules! impl_last_arg {{
+    ($($ty:ty),*) => {{
+        $(impl LastArg for $ty {{
+            fn pack(self) -> u64 {{
+                self as u64
+            }}
+            fn unpack(bits: u64) -> $ty {{
+                bits as $ty
+            }}
+        }})*
+    }};
+}}
+
+impl_last_arg!(i8, u8, i16, u16, i32, u32, i64, u64, isize, usize);
+
+impl LastArg for f32 {{
+    fn pack(self) -> u64 {{
+        u64::from(self.to_bits())
+    }}
+    fn unpack(bits: u64) -> f32 {{
+        f32::from_bits(bits as u32)
+    }}
+}}
+
+impl LastArg for f64 {{
+    fn pack(self) -> u64 {{
+        self.to_bits()
+    }}
+    fn unpack(bits: u64) -> f64 {{
+        f64::from_bits(bits)
+    }}
+}}
+
+impl<T> LastArg for *const T {{
+    fn pack(self) -> u64 {{
+        self as usize as u64
+    }}
+    fn unpack(bits: u64) -> *const T {{
+        bits as usize as *const T
+    }}
+}}
+
+impl<T> LastArg for *mut T {{
+    fn pack(self) -> u64 {{
+        self as usize as u64
+    }}
+    fn unpack(bits: u64) -> *mut T {{
+        bits as usize as *mut T
+    }}
+}}
+
+/// Returns the arguments of the last call of the named command (e.g. `"glTexImage2D"`), or `None`
+/// if there is no such command or it was not called since the last reset.
+pub fn {profiler_last_args}(name: &str) -> Option<String> {{
+    let index = command_index(name)?;
+    if LAST_ARGS_CALLED[index].load(::std::sync::atomic::Ordering::Acquire) {{
+        Some(format_last_args(index))
+    }} else {{
+        None
+    }}
+}}
+
+fn record_last_args(index: usize, offset: usize, args: &[u64]) {{
+    for (slot, &bits) in LAST_ARGS[offset..].iter().zip(args) {{
+        slot.store(bits, ::std::sync::atomic::Ordering::Relaxed);
+    }}
+    LAST_ARGS_CALLED[index].store(true, ::std::sync::atomic::Ordering::Release);
+}}
+
+fn format_last_args(index: usize) -> String {{
+    #[allow(unused_variables)]
+    let arg = |offset: usize| LAST_ARGS[offset].load(::std::sync::atomic::Ordering::Relaxed);
+    match index {{"##,
+        total = registry.cmds.iter().map(|cmd| cmd.params.len()).sum::<usize>(),
+        count = registry.cmds.len(),
+        profiler_last_args = gen.fn_name("last_args"),
+    )?;
+
+    let enum_groups = gen.enum_groups(registry);
+    let mut offset = 0;
+    for (index, cmd) in registry.cmds.iter().enumerate() {
+        if cmd.params.is_empty() {
+            continue;
+        }
+        let args = gen_parameters(cmd, false, true)
+            .iter()
+            .zip(cmd.params.iter())
+            .enumerate()
+            .map(|(i, (ty, binding))| if ty.contains("GLDEBUGPROC") {
+                ", \"<callback>\"".to_string()
+            } else {
+                let value = format!("<{} as LastArg>::unpack(arg({}))", ty, offset + i);
+                match enum_group(registry, binding) {
+                    Some(group) if gen.enum_names && enum_groups.contains_key(group.ident.as_str()) => {
+                        format!(", EnumArg({}, ENUMS_{})", value, group.ident)
+                    }
+                    _ => format!(", {}", value),
+                }
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        writeln!(
+            dest,
+            "        {} => format!(\"{}\"{}),",
+            index,
+            vec!["{:?}"; cmd.params.len()].join(", "),
+            args
+        )?;
+        offset += cmd.params.len();
+    }
+    writeln!(
+        dest,
+        "        _ => String::new(),
+    }}
+}}"
+    )
+}
+
+/// Creates the flag which makes the wrappers skip the GL functions.
+pub(crate) fn write_dry_run_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+static DRY_RUN: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+
+/// Starts or stops the dry run, in which the calls are counted but the GL functions are not
+/// called. The commands then return zero or null, so their results are meaningless.
+pub fn {profiler_set_dry_run}(enabled: bool) {{
+    DRY_RUN.store(enabled, ::std::sync::atomic::Ordering::SeqCst);
+}}
+
+fn dry_run() -> bool {{
+    DRY_RUN.load(::std::sync::atomic::Ordering::Relaxed)
+}}
+"##,
+        profiler_set_dry_run = gen.fn_name("set_dry_run"),
+    )
+}
+
+/// Creates the flags which turn the instrumentation of each command on or off.
+pub(crate) fn write_command_toggle_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+static COMMANDS_ENABLED: [::std::sync::atomic::AtomicBool; {count}] = {{
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ON: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(true);
+    [ON; {count}]
+}};
+
+/// Turns the instrumentation of the named command (e.g. `"glDrawArrays"`) off or on. Returns
+/// `false` if there is no such command.
+pub fn {profiler_set_command_enabled}(name: &str, enabled: bool) -> bool {{
+    match command_index(name) {{
+        Some(i) => {{
+            COMMANDS_ENABLED[i].store(enabled, ::std::sync::atomic::Ordering::SeqCst);
+            true
+        }}
+        None => false,
+    }}
+}}
+
+fn command_enabled(index: usize) -> bool {{
+    COMMANDS_ENABLED[index].load(::std::sync::atomic::Ordering::Relaxed)
+}}"##,
+        count = registry.cmds.len(),
+        profiler_set_command_enabled = gen.fn_name("set_command_enabled"),
+    )
+}
+
+/// Creates the counter of the calls which repeat the arguments of the previous call.
+///
+/// The previous arguments are kept by the wrappers, which know their types.
+pub(crate) fn write_redundant_call_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+{redundant_calls}
+
+/// Returns the number of calls made with the same arguments as the previous call of the command
+/// on the same thread, among the commands checked for redundant calls.
+pub fn {profiler_redundant_call_count}() -> usize {{
+    REDUNDANT_CALLS.load(::std::sync::atomic::Ordering::SeqCst)
+}}
+
+#[allow(dead_code)]
+fn inc_redundant_call() {{
+    REDUNDANT_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+}}"##,
+        redundant_calls = gen_counter_static(gen, "REDUNDANT_CALLS", "usize", None),
+        profiler_redundant_call_count = gen.fn_name("redundant_call_count"),
+    )
+}
+
+/// Creates the frame boundary, which checks the number of calls of the frame against the call
+/// budget.
+pub(crate) fn write_budget_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let log = if gen.stack_error_buffer > 0 {
+        format!(
+            r#"log_error(format_args!("{} exceeded GL call budget ({{}}): {{}} calls this frame", budget, calls));"#,
+            gen.log_prefix(registry)
+        )
+    } else {
+        format!(
+            r#"println!("{} exceeded GL call budget ({{}}): {{}} calls this frame", budget, calls);"#,
+            gen.log_prefix(registry)
+        )
+    };
+
+    writeln!(
+        dest,
+        r##"
+{call_budget}
+{frame_start_calls}
+
+/// Sets the number of calls allowed per frame, checked by `{profiler_frame_end}()`. Zero disables
+/// the check.
+pub fn {profiler_set_call_budget}(calls: usize) {{
+    CALL_BUDGET.store(calls, ::std::sync::atomic::Ordering::SeqCst);
+}}
+
+/// Marks the end of a frame, and returns the number of calls made during it.
+///
+/// If the calls exceed the budget set with `{profiler_set_call_budget}`, a warning is logged.
+pub fn {profiler_frame_end}() -> usize {{
+    let total = {profiler_call_count}();
+    let calls = total.saturating_sub(FRAME_START_CALLS.load(::std::sync::atomic::Ordering::SeqCst));
+    FRAME_START_CALLS.store(total, ::std::sync::atomic::Ordering::SeqCst);{record_frame}{dump}
+    let budget = CALL_BUDGET.load(::std::sync::atomic::Ordering::SeqCst);
+    if budget > 0 && calls > budget {{
+        {log}
+    }}
+    calls
+}}"##,
+        call_budget = gen_counter_static(gen, "CALL_BUDGET", "usize", None),
+        frame_start_calls = gen_counter_static(gen, "FRAME_START_CALLS", "usize", None),
+        record_frame = if gen.frame_history > 0 { "\n    record_frame(calls);" } else { "" },
+        dump = if gen.signal_dump { format!("\n    {}();", gen.fn_name("dump_if_requested")) } else { String::new() },
+        log = log,
+        profiler_frame_end = gen.fn_name("frame_end"),
+        profiler_set_call_budget = gen.fn_name("set_call_budget"),
+        profiler_call_count = gen.fn_name("call_count"),
+    )
+}
+
+/// Creates the `LocalCounter`, which replaces the atomic counters in the single-threaded mode.
+///
+/// It has the same methods as the atomics, so the code which uses the counters is the same in
+/// both modes.
+pub(crate) fn write_local_counter_helper<W>(dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+/// A counter in a thread-local array of `Cell`s, with the interface of the atomics.
+#[derive(Clone, Copy)]
+struct LocalCounter<T: 'static, const N: usize> {
+    cells: &'static ::std::thread::LocalKey<[::std::cell::Cell<T>; N]>,
+    index: usize,
+}
+
+#[allow(dead_code)]
+impl<T: Copy + ::std::ops::Add<Output = T>, const N: usize> LocalCounter<T, N> {
+    fn load(&self, _: ::std::sync::atomic::Ordering) -> T {
+        self.cells.with(|cells| cells[self.index].get())
+    }
+
+    fn store(&self, value: T, _: ::std::sync::atomic::Ordering) {
+        self.cells.with(|cells| cells[self.index].set(value))
+    }
+
+    fn fetch_add(&self, value: T, _: ::std::sync::atomic::Ordering) -> T {
+        self.cells.with(|cells| {
+            let previous = cells[self.index].get();
+            cells[self.index].set(previous + value);
+            previous
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Copy + Ord, const N: usize> LocalCounter<T, N> {
+    fn fetch_min(&self, value: T, _: ::std::sync::atomic::Ordering) -> T {
+        self.cells.with(|cells| cells[self.index].replace(cells[self.index].get().min(value)))
+    }
+
+    fn fetch_max(&self, value: T, _: ::std::sync::atomic::Ordering) -> T {
+        self.cells.with(|cells| cells[self.index].replace(cells[self.index].get().max(value)))
+    }
+}
+
+/// Creates one counter for each of the `cells`.
+#[allow(dead_code)]
+const fn local_counters<T: Copy, const N: usize>(
+    cells: &'static ::std::thread::LocalKey<[::std::cell::Cell<T>; N]>,
+) -> [LocalCounter<T, N>; N] {
+    let mut counters = [LocalCounter { cells, index: 0 }; N];
+    let mut i = 0;
+    while i < N {
+        counters[i].index = i;
+        i += 1;
+    }
+    counters
+}
+    "##
+        .as_bytes(),
+    )
+}
+
+/// Creates the error counter, either a global one or one per thread.
+pub(crate) fn write_err_count_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    if !gen.thread_local_errors {
+        writeln!(dest, "{}", gen_counter_static(gen, "ERR_COUNT", "usize", None))?;
+        return write!(
+            dest,
+            r##"
+pub fn {profiler_err_count}() -> usize {{
+    ERR_COUNT.load(::std::sync::atomic::Ordering::SeqCst)
+}}
+
+/// Returns the same as `{profiler_err_count}`, with a `Relaxed` load.
+pub fn {profiler_err_count_relaxed}() -> usize {{
+    ERR_COUNT.load(::std::sync::atomic::Ordering::Relaxed)
+}}
+
+#[allow(dead_code)]
+fn inc_err() {{
+    ERR_COUNT.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+}}
+    "##,
+            profiler_err_count = gen.fn_name("err_count"),
+            profiler_err_count_relaxed = gen.fn_name("err_count_relaxed"),
+        );
+    }
+
+    write!(
+        dest,
+        r##"
+/// The error counters of all threads which have reported an error. The counters of the threads
+/// which have exited are kept, so that their errors are still included in the total.
+static ERR_COUNTS_BY_THREAD: ::std::sync::Mutex<Vec<::std::sync::Arc<::std::sync::atomic::AtomicUsize>>> =
+    ::std::sync::Mutex::new(Vec::new());
+
+thread_local! {{
+    static THREAD_ERR_COUNT: ::std::sync::Arc<::std::sync::atomic::AtomicUsize> = {{
+        let counter = ::std::sync::Arc::new(::std::sync::atomic::AtomicUsize::new(0));
+        ERR_COUNTS_BY_THREAD.lock().unwrap_or_else(|e| e.into_inner()).push(counter.clone());
+        counter
+    }};
+}}
+
+/// Returns the number of errors of all threads.
+pub fn {profiler_err_count}() -> usize {{
+    ERR_COUNTS_BY_THREAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|c| c.load(::std::sync::atomic::Ordering::SeqCst))
+        .sum()
+}}
+
+/// Returns the same as `{profiler_err_count}`, with `Relaxed` loads.
+pub fn {profiler_err_count_relaxed}() -> usize {{
+    ERR_COUNTS_BY_THREAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|c| c.load(::std::sync::atomic::Ordering::Relaxed))
+        .sum()
+}}
+
+/// Returns the number of errors of the calling thread.
+pub fn {profiler_err_count_this_thread}() -> usize {{
+    THREAD_ERR_COUNT.with(|c| c.load(::std::sync::atomic::Ordering::SeqCst))
+}}
+
+#[allow(dead_code)]
+fn inc_err() {{
+    THREAD_ERR_COUNT.with(|c| c.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst));
+}}
+    "##,
+        profiler_err_count = gen.fn_name("err_count"),
+        profiler_err_count_relaxed = gen.fn_name("err_count_relaxed"),
+        profiler_err_count_this_thread = gen.fn_name("err_count_this_thread"),
+    )
+}
+
+/// Creates `count_error`, through which every counted error goes, so that the error hook sees
+/// the same errors as the counter.
+pub(crate) fn write_count_error_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+#[allow(dead_code)]
+fn count_error({error}: u32) {{
+    inc_err();{run_err_hook}
+}}"##,
+        error = if gen.count_hooks { "error" } else { "_error" },
+        run_err_hook = if gen.count_hooks { "\n    run_err_hook(error);" } else { "" }
+    )
+}
+
+/// Creates the `InstanceCounters`, which the generated struct holds when the instance counters are
+/// enabled.
+pub(crate) fn write_instance_counters_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+/// The calls and errors counted by one instance of the bindings.
+struct InstanceCounters {
+    calls: ::std::sync::atomic::AtomicUsize,
+    errors: ::std::sync::atomic::AtomicUsize,
+}
+
+impl InstanceCounters {
+    fn new() -> InstanceCounters {
+        InstanceCounters {
+            calls: ::std::sync::atomic::AtomicUsize::new(0),
+            errors: ::std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    fn inc_call(&self) {
+        self.calls.fetch_add(CALL_WEIGHT, ::std::sync::atomic::Ordering::SeqCst);
+    }
+
+    #[allow(dead_code)]
+    fn inc_err(&self) {
+        self.errors.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// A clone starts with the counts of the original.
+impl Clone for InstanceCounters {
+    fn clone(&self) -> InstanceCounters {
+        InstanceCounters {
+            calls: ::std::sync::atomic::AtomicUsize::new(self.calls.load(::std::sync::atomic::Ordering::SeqCst)),
+            errors: ::std::sync::atomic::AtomicUsize::new(self.errors.load(::std::sync::atomic::Ordering::SeqCst)),
+        }
+    }
+}
+    "##
+        .replace("CALL_WEIGHT", gen.call_weight())
+        .as_bytes(),
+    )
+}
+
+/// Creates the named scopes, which accumulate the calls and errors made while they are alive.
+pub(crate) fn write_scope_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+/// The calls and errors accumulated by a named scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]{serde}
+pub struct ScopeStats {{
+    /// The number of times the scope has been entered.
+    pub entries: usize,
+    /// The number of calls made inside the scope.
+    pub calls: usize,
+    /// The number of errors inside the scope.
+    pub errors: usize,
+}}"##,
+        serde = gen.serde_attr()
+    )?;
+
+    write!(
+        dest,
+        r##"
+static SCOPES: ::std::sync::Mutex<Vec<(&'static str, ScopeStats)>> = ::std::sync::Mutex::new(Vec::new());
+
+thread_local! {{
+    static SCOPE_DEPTH: ::std::cell::Cell<usize> = const {{ ::std::cell::Cell::new(0) }};
+}}
+
+/// The nesting depth above which the scopes are assumed to be leaking, in debug builds.
+const MAX_SCOPE_DEPTH: usize = 256;
+
+/// A named scope, created by `{profiler_scope}`, which adds the calls and errors made while it is
+/// alive to the stats of its name when dropped.
+#[must_use]
+pub struct ProfilerScope {{
+    name: &'static str,
+    calls: usize,
+    errors: usize,
+}}
+
+impl Drop for ProfilerScope {{
+    fn drop(&mut self) {{
+        SCOPE_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+        let calls = {profiler_call_count}().saturating_sub(self.calls);
+        let errors = {profiler_err_count}().saturating_sub(self.errors);
+        let mut scopes = SCOPES.lock().unwrap_or_else(|e| e.into_inner());
+        let index = match scopes.iter().position(|&(name, _)| name == self.name) {{
+            Some(index) => index,
+            None => {{
+                scopes.push((self.name, ScopeStats::default()));
+                scopes.len() - 1
+            }}
+        }};
+        let stats = &mut scopes[index].1;
+        stats.entries += 1;
+        stats.calls += calls;
+        stats.errors += errors;
+    }}
+}}
+
+/// Enters the named scope until the returned guard is dropped.
+///
+/// The scope measures the counters of the whole profiler, so the calls made by other threads
+/// meanwhile are included too.
+pub fn {profiler_scope}(name: &'static str) -> ProfilerScope {{
+    enter_scope(name)
+}}
+
+fn enter_scope(name: &'static str) -> ProfilerScope {{
+    let depth = SCOPE_DEPTH.with(|depth| {{
+        depth.set(depth.get() + 1);
+        depth.get()
+    }});
+    debug_assert!(
+        depth <= MAX_SCOPE_DEPTH,
+        "{{}} nested scopes entered, the innermost being {{:?}}: are the scope guards leaking?",
+        depth,
+        name
+    );
+    ProfilerScope {{
+        name,
+        calls: {profiler_call_count}(),
+        errors: {profiler_err_count}(),
+    }}
+}}
+
+/// Returns the number of scopes entered and not exited yet on this thread, which is back at the
+/// same value after a balanced piece of code.
+pub fn {profiler_scope_depth}() -> usize {{
+    SCOPE_DEPTH.with(|depth| depth.get())
+}}
+
+/// Panics if a scope entered on this thread has not been exited yet, e.g. in the teardown of a
+/// test, or at the end of a frame in debug builds.
+#[track_caller]
+pub fn {profiler_assert_balanced_scopes}() {{
+    let depth = {profiler_scope_depth}();
+    assert!(depth == 0, "{{}} scopes entered and not exited on this thread: is a scope guard kept alive?", depth);
+}}
+
+/// Returns the stats of the named scope, or `None` if it has not been exited yet.
+pub fn {profiler_scope_stats}(name: &str) -> Option<ScopeStats> {{
+    SCOPES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, stats)| stats)
+}}
+
+/// Returns the stats of all scopes, in the order they were first exited.
+pub fn {profiler_scopes}() -> Vec<(&'static str, ScopeStats)> {{
+    SCOPES.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}}
+
+/// Resets the stats of the named scope only, e.g. after loading a level.
+pub fn {profiler_scope_reset}(name: &str) {{
+    for scope in SCOPES.lock().unwrap_or_else(|e| e.into_inner()).iter_mut() {{
+        if scope.0 == name {{
+            scope.1 = ScopeStats::default();
+        }}
+    }}
+}}
+    "##,
+        profiler_scope = gen.fn_name("scope"),
+        profiler_call_count = gen.fn_name("call_count"),
+        profiler_err_count = gen.fn_name("err_count"),
+        profiler_scope_depth = gen.fn_name("scope_depth"),
+        profiler_assert_balanced_scopes = gen.fn_name("assert_balanced_scopes"),
+        profiler_scope_stats = gen.fn_name("scope_stats"),
+        profiler_scopes = gen.fn_name("scopes"),
+        profiler_scope_reset = gen.fn_name("scope_reset"),
+    )
+}
+
+/// Creates the signal handler which requests a dump of the profiler.
+///
+/// The handler is registered with the `signal` function of the C library, declared here so that
+/// the bindings do not depend on the `libc` crate.
+pub(crate) fn write_signal_dump_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+static DUMP_REQUESTED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+
+/// Makes the signal `signum` (e.g. `10`, `SIGUSR1` on Linux) request a dump of the profiler, printed by
+/// the next `{profiler_frame_end}()` or `{profiler_dump_if_requested}()`. Returns `false` if the
+/// handler could not be installed.
+///
+/// The handler only sets a flag, since printing is not async-signal-safe. It replaces the
+/// previous handler of the signal.
+#[cfg(unix)]
+pub fn {profiler_install_signal_handler}(signum: i32) -> bool {{
+    unsafe extern "C" {{
+        fn signal(signum: ::std::os::raw::c_int, handler: extern "C" fn(::std::os::raw::c_int)) -> usize;
+    }}
+    extern "C" fn request_dump(_: ::std::os::raw::c_int) {{
+        DUMP_REQUESTED.store(true, ::std::sync::atomic::Ordering::SeqCst);
+    }}
+    // `SIG_ERR` is -1
+    unsafe {{ signal(signum, request_dump) != usize::MAX }}
+}}
+
+/// Prints the summary and the table of the profiler if a dump was requested by the signal, and
+/// returns whether it was. Call it from a point where printing is safe, e.g. once per frame.
+pub fn {profiler_dump_if_requested}() -> bool {{
+    if !DUMP_REQUESTED.swap(false, ::std::sync::atomic::Ordering::SeqCst) {{
+        return false;
+    }}
+    println!("{{}}", {profiler_summary}());
+    {profiler_print_table}();
+    true
+}}
+"##,
+        profiler_frame_end = gen.fn_name("frame_end"),
+        profiler_dump_if_requested = gen.fn_name("dump_if_requested"),
+        profiler_install_signal_handler = gen.fn_name("install_signal_handler"),
+        profiler_summary = gen.fn_name("summary"),
+        profiler_print_table = gen.fn_name("print_table"),
+    )
+}
+
+/// Creates the counters of the command categories.
+pub(crate) fn write_category_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let (names, _) = gen.categories(registry);
+    writeln!(
+        dest,
+        r##"
+static CATEGORY_NAMES: [&str; {count}] = [{names}];
+{category_counts}
+
+/// Returns the call counts of the categories of commands, e.g. `("draw", 120)`, ordered by the
+/// names of the categories.
+pub fn {profiler_category_counts}() -> Vec<(&'static str, usize)> {{
+    CATEGORY_NAMES
+        .iter()
+        .zip(CATEGORY_COUNTS.iter())
+        .map(|(&name, count)| (name, count.load(::std::sync::atomic::Ordering::SeqCst)))
+        .collect()
+}}
+
+fn inc_category_call(index: usize) {{
+    CATEGORY_COUNTS[index].fetch_add({weight}, ::std::sync::atomic::Ordering::SeqCst);
+}}"##,
+        weight = gen.call_weight(),
+        count = names.len(),
+        names = names.iter().map(|name| format!("{:?}", name)).collect::<Vec<_>>().join(", "),
+        category_counts = gen_counter_static(gen, "CATEGORY_COUNTS", "usize", Some(names.len())),
+        profiler_category_counts = gen.fn_name("category_counts"),
+    )
+}
+
+/// Creates the fixed-capacity buffers which keep the recent errors and the call trace.
+pub(crate) fn write_buffers_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    dest.write_all(
+        r##"
+struct Ring<T: Copy, const N: usize> {
+    items: [T; N],
+    start: usize,
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> Ring<T, N> {
+    const fn new(empty: T) -> Self {
+        Ring { items: [empty; N], start: 0, len: 0 }
+    }
+
+    fn push(&mut self, item: T) {
+        self.items[(self.start + self.len) % N] = item;
+        if self.len == N {
+            self.start = (self.start + 1) % N;
+        } else {
+            self.len += 1;
+        }
+    }
+
+    #[allow(dead_code)]
+    fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    fn to_vec(&self) -> Vec<T> {
+        (0..self.len).map(|i| self.items[(self.start + i) % N]).collect()
+    }
+}
+"##
+        .as_bytes(),
+    )?;
+
+    if gen.error_buffer_size > 0 {
+        writeln!(
+            dest,
+            r##"
+static RECENT_ERRORS: ::std::sync::Mutex<Ring<(&'static str, u32), {size}>> = ::std::sync::Mutex::new(Ring::new(("", 0)));
+
+pub fn {profiler_recent_errors}() -> Vec<(&'static str, u32)> {{
+    RECENT_ERRORS.lock().unwrap_or_else(|e| e.into_inner()).to_vec()
+}}
+
+#[allow(dead_code)]
+fn record_recent_error(command: &'static str, error: u32) {{
+    RECENT_ERRORS.lock().unwrap_or_else(|e| e.into_inner()).push((command, error));
+}}"##,
+            size = gen.error_buffer_size,
+            profiler_recent_errors = gen.fn_name("recent_errors"),
+        )?;
+    }
+
+    if gen.trace_capacity > 0 {
+        writeln!(
+            dest,
+            r##"
+static TRACE: ::std::sync::Mutex<Ring<&'static str, {capacity}>> = ::std::sync::Mutex::new(Ring::new(""));
+
+pub fn {profiler_trace}() -> Vec<&'static str> {{
+    TRACE.lock().unwrap_or_else(|e| e.into_inner()).to_vec()
+}}
+
+fn record_trace(command: &'static str) {{
+    TRACE.lock().unwrap_or_else(|e| e.into_inner()).push(command);
+}}"##,
+            capacity = gen.trace_capacity,
+            profiler_trace = gen.fn_name("trace"),
+        )?;
+    }
+
+    if gen.has_error_context(registry) {
+        writeln!(
+            dest,
+            r##"
+thread_local! {{
+    static RECENT_COMMANDS: ::std::cell::RefCell<Ring<&'static str, {depth}>> = const {{ ::std::cell::RefCell::new(Ring::new("")) }};
+}}
+
+fn record_recent_command(command: &'static str) {{
+    RECENT_COMMANDS.with(|recent| recent.borrow_mut().push(command));
+}}
+
+/// Returns the last commands called on the current thread, oldest first, separated by commas.
+fn recent_commands() -> String {{
+    RECENT_COMMANDS.with(|recent| recent.borrow().to_vec().join(", "))
+}}"##,
+            depth = gen.error_context_depth
+        )?;
+    }
+
+    if gen.frame_history > 0 {
+        writeln!(
+            dest,
+            r##"
+static FRAME_HISTORY: ::std::sync::Mutex<Ring<usize, {frames}>> = ::std::sync::Mutex::new(Ring::new(0));
+
+/// Returns the call counts of the last frames ended by `{profiler_frame_end}()`, oldest first.
+pub fn {profiler_frame_history}() -> Vec<usize> {{
+    FRAME_HISTORY.lock().unwrap_or_else(|e| e.into_inner()).to_vec()
+}}
+
+fn record_frame(calls: usize) {{
+    FRAME_HISTORY.lock().unwrap_or_else(|e| e.into_inner()).push(calls);
+}}"##,
+            frames = gen.frame_history,
+            profiler_frame_end = gen.fn_name("frame_end"),
+            profiler_frame_history = gen.fn_name("frame_history"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates the `profiler_gl_info()` function, which returns the strings captured by the
+/// `profiler_capture_gl_info(&self)` method.
+pub(crate) fn write_gl_info_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    write!(
+        dest,
+        r##"
+static GL_INFO: ::std::sync::Mutex<Option<(String, String, String)>> = ::std::sync::Mutex::new(None);
+
+/// Returns the `GL_VENDOR`, `GL_RENDERER` and `GL_VERSION` strings captured by the last
+/// `{profiler_capture_gl_info}()`, or `None` if they were not captured.
+#[allow(dead_code)]
+pub fn {profiler_gl_info}() -> Option<(String, String, String)> {{
+    GL_INFO.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}}
+
+fn set_gl_info(info: (String, String, String)) {{
+    *GL_INFO.lock().unwrap_or_else(|e| e.into_inner()) = Some(info);
+}}
+"##,
+        profiler_capture_gl_info = gen.fn_name("capture_gl_info"),
+        profiler_gl_info = gen.fn_name("gl_info"),
+    )
+}
+
+/// Creates the flags which mark the commands usable in the probed context.
+pub(crate) fn write_usability_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let required_versions: Vec<_> = registry
+        .cmds
+        .iter()
+        .enumerate()
+        .filter_map(|(index, cmd)| {
+            gen.required_version(registry, cmd)
+                .map(|(major, minor)| format!("({}, ({}, {}))", index, major, minor))
+        })
+        .collect();
+    writeln!(
+        dest,
+        r##"
+/// The indices of the commands introduced by a later version of the API, with that version.
+const COMMAND_REQUIRED_VERSIONS: &[(usize, (u32, u32))] = &[{required_versions}];
+
+static COMMANDS_USABLE: [::std::sync::atomic::AtomicBool; {count}] = {{
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ON: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(true);
+    [ON; {count}]
+}};
+
+/// Returns whether the named command (e.g. `"glDispatchCompute"`) is usable in the context
+/// probed by `{profiler_probe_usable}()`, or `None` if there is no such command. All the commands
+/// are assumed usable until the probe.
+pub fn {profiler_command_usable}(name: &str) -> Option<bool> {{
+    command_index(name).map(|i| COMMANDS_USABLE[i].load(::std::sync::atomic::Ordering::SeqCst))
+}}
+
+/// Marks the named command as usable or not, e.g. after checking an extension. Returns `false`
+/// if there is no such command.
+pub fn {profiler_set_command_usable}(name: &str, usable: bool) -> bool {{
+    match command_index(name) {{
+        Some(i) => {{
+            COMMANDS_USABLE[i].store(usable, ::std::sync::atomic::Ordering::SeqCst);
+            true
+        }}
+        None => false,
+    }}
+}}
+
+/// Returns the names of the commands which are not usable in the probed context.
+pub fn {profiler_unusable_commands}() -> Vec<&'static str> {{
+    COMMAND_NAMES
+        .iter()
+        .zip(COMMANDS_USABLE.iter())
+        .filter(|(_, usable)| !usable.load(::std::sync::atomic::Ordering::SeqCst))
+        .map(|(&name, _)| name)
+        .collect()
+}}
+
+/// Marks each command as usable if it is loaded and the context `version` (e.g. `"4.6.0 NVIDIA"`
+/// or `"OpenGL ES 3.2 Mesa"`) is recent enough for it. An unknown version passes.
+fn mark_usable_commands(version: &str, is_loaded: impl Fn(usize) -> bool) {{
+    let version = parse_version(version);
+    for (index, usable) in COMMANDS_USABLE.iter().enumerate() {{
+        let required = COMMAND_REQUIRED_VERSIONS.iter().find(|&&(i, _)| i == index).map(|&(_, v)| v);
+        let recent_enough = match (required, version) {{
+            (Some(required), Some(version)) => version >= required,
+            _ => true,
+        }};
+        usable.store(is_loaded(index) && recent_enough, ::std::sync::atomic::Ordering::SeqCst);
+    }}
+}}
+
+/// Parses the first `major.minor` of a `GL_VERSION` string.
+fn parse_version(version: &str) -> Option<(u32, u32)> {{
+    let start = version.find(|c: char| c.is_ascii_digit())?;
+    let mut numbers = version[start..].split(|c: char| !c.is_ascii_digit());
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+    Some((major, minor))
+}}"##,
+        required_versions = required_versions.join(", "),
+        count = registry.cmds.len(),
+        profiler_probe_usable = gen.fn_name("probe_usable"),
+        profiler_command_usable = gen.fn_name("command_usable"),
+        profiler_set_command_usable = gen.fn_name("set_command_usable"),
+        profiler_unusable_commands = gen.fn_name("unusable_commands"),
+    )
+}
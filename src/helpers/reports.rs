@@ -0,0 +1,544 @@
+//! Writers of the reports, snapshots and exports of the profiler counters.
+
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+
+/// Creates the functions which export the profiler state, and the baseline check which reads the
+/// JSON export back.
+///
+/// See the README for the layout of `profiler_to_bytes`.
+pub(crate) fn write_export_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let flags = gen.cpu_timing as u16 | (gen.per_command_counts as u16) << 1;
+    let time = if gen.cpu_timing { format!("{}()", gen.fn_name("time_ns")) } else { "0".to_string() };
+    let commands = match (gen.per_command_counts, gen.cpu_timing) {
+        (true, true) => "
+    let commands: Vec<_> = (0..COMMAND_NAMES.len())
+        .map(|i| (
+            COMMAND_NAMES[i],
+            CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) as u64,
+            ERR_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) as u64,
+            COMMAND_TIME_NS[i].load(::std::sync::atomic::Ordering::SeqCst),
+        ))
+        .filter(|&(_, calls, errors, _)| calls > 0 || errors > 0)
+        .collect();",
+        (true, false) => "
+    let commands: Vec<_> = (0..COMMAND_NAMES.len())
+        .map(|i| (
+            COMMAND_NAMES[i],
+            CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) as u64,
+            ERR_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) as u64,
+            0u64,
+        ))
+        .filter(|&(_, calls, errors, _)| calls > 0 || errors > 0)
+        .collect();",
+        (false, _) => "
+    let commands: Vec<(&str, u64, u64, u64)> = Vec::new();",
+    };
+
+    writeln!(
+        dest,
+        r##"
+/// Encodes the profiler counters in a compact little-endian binary format, which is described in
+/// the documentation of `gl_generator_profiling_struct`.
+pub fn {profiler_to_bytes}() -> Vec<u8> {{{commands}
+    let mut bytes = Vec::with_capacity(40 + commands.len() * 64);
+    bytes.extend_from_slice(b"GLPR");
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&{flags}u16.to_le_bytes());
+    bytes.extend_from_slice(&({profiler_call_count}() as u64).to_le_bytes());
+    bytes.extend_from_slice(&({profiler_err_count}() as u64).to_le_bytes());
+    bytes.extend_from_slice(&({time} as u64).to_le_bytes());
+    bytes.extend_from_slice(&(commands.len() as u32).to_le_bytes());
+    for (name, calls, errors, time_ns) in commands {{
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&calls.to_le_bytes());
+        bytes.extend_from_slice(&errors.to_le_bytes());
+        bytes.extend_from_slice(&time_ns.to_le_bytes());
+    }}
+    bytes
+}}"##,
+        commands = commands,
+        flags = flags,
+        time = time,
+        profiler_to_bytes = gen.fn_name("to_bytes"),
+        profiler_call_count = gen.fn_name("call_count"),
+        profiler_err_count = gen.fn_name("err_count"),
+    )?;
+
+    writeln!(
+        dest,
+        r##"
+/// Encodes the profiler counters as JSON, with the same content as `{profiler_to_bytes}`, e.g. to
+/// be kept as the baseline of `{profiler_check_against_baseline}`.
+pub fn {profiler_to_json}() -> String {{{commands}
+    use ::std::fmt::Write;
+    let mut json = String::with_capacity(64 + commands.len() * 80);
+    let _ = write!(
+        json,
+        "{{{{\"calls\":{{}},\"errors\":{{}},\"time_ns\":{{}},\"commands\":[",
+        {profiler_call_count}(),
+        {profiler_err_count}(),
+        {time},
+    );
+    for (i, (name, calls, errors, time_ns)) in commands.into_iter().enumerate() {{
+        let _ = write!(
+            json,
+            "{{}}{{{{\"name\":\"{{}}\",\"calls\":{{}},\"errors\":{{}},\"time_ns\":{{}}}}}}",
+            if i == 0 {{ "" }} else {{ "," }},
+            name,
+            calls,
+            errors,
+            time_ns,
+        );
+    }}
+    json.push_str("]}}");
+    json
+}}"##,
+        commands = commands,
+        time = time,
+        profiler_to_bytes = gen.fn_name("to_bytes"),
+        profiler_to_json = gen.fn_name("to_json"),
+        profiler_check_against_baseline = gen.fn_name("check_against_baseline"),
+        profiler_call_count = gen.fn_name("call_count"),
+        profiler_err_count = gen.fn_name("err_count"),
+    )?;
+
+    let per_command = if gen.per_command_counts {
+        "
+    for (i, &name) in COMMAND_NAMES.iter().enumerate() {
+        let before = commands.iter().find(|(n, _)| n == name).map_or(0, |&(_, calls)| calls);
+        let now = CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) as u64;
+        if grew(before, now) {
+            regressions.push(format!(\"{}: {} calls in the baseline, {} now\", name, before, now));
+        }
+    }"
+    } else {
+        ""
+    };
+    write!(
+        dest,
+        r##"
+/// Compares the current call counts with a baseline exported by `{profiler_to_json}`, e.g. in an
+/// earlier run, and returns the description of the total and of each command whose calls grew
+/// by more than `tolerance` (e.g. `0.1` for 10%), or of the baseline if it can not be parsed.
+pub fn {profiler_check_against_baseline}(json: &str, tolerance: f64) -> Result<(), Vec<String>> {{
+    let (calls, {commands}) = match parse_call_counts(json) {{
+        Some(parsed) => parsed,
+        None => return Err(vec!["the baseline is not exported by `{profiler_to_json}`".to_string()]),
+    }};
+    let grew = |before: u64, now: u64| now as f64 > before as f64 * (1.0 + tolerance);
+    let mut regressions = Vec::new();
+    let now = {profiler_call_count}() as u64;
+    if grew(calls, now) {{
+        regressions.push(format!("all commands: {{}} calls in the baseline, {{}} now", calls, now));
+    }}{per_command}
+    if regressions.is_empty() {{
+        Ok(())
+    }} else {{
+        Err(regressions)
+    }}
+}}
+
+/// Parses the total and the per-command call counts exported by `{profiler_to_json}`.
+///
+/// Only that layout is understood: the same keys in the same order, with no other field, and
+/// names without escapes. Whitespace is allowed between the tokens, e.g. after pretty-printing,
+/// but not inside them. Anything else returns `None`.
+fn parse_call_counts(json: &str) -> Option<(u64, Vec<(String, u64)>)> {{
+    struct Parser<'a> {{
+        rest: &'a str,
+    }}
+
+    impl<'a> Parser<'a> {{
+        fn token(&mut self, token: &str) -> Option<()> {{
+            self.rest = self.rest.trim_start().strip_prefix(token)?;
+            Some(())
+        }}
+
+        fn string(&mut self) -> Option<&'a str> {{
+            self.token("\"")?;
+            let end = self.rest.find(|c| c == '"' || c == '\\')?;
+            let (value, rest) = self.rest.split_at(end);
+            // a backslash, which would start an escape, fails here
+            self.rest = rest.strip_prefix('"')?;
+            Some(value)
+        }}
+
+        fn number(&mut self) -> Option<u64> {{
+            let rest = self.rest.trim_start();
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let (digits, rest) = rest.split_at(end);
+            self.rest = rest;
+            digits.parse().ok()
+        }}
+
+        /// Parses `"key":` followed by a number, after a `,` unless it is the first field.
+        fn field(&mut self, key: &str, first: bool) -> Option<u64> {{
+            if !first {{
+                self.token(",")?;
+            }}
+            self.key(key)?;
+            self.number()
+        }}
+
+        fn key(&mut self, key: &str) -> Option<()> {{
+            if self.string()? != key {{
+                return None;
+            }}
+            self.token(":")
+        }}
+    }}
+
+    let mut parser = Parser {{ rest: json }};
+    parser.token("{{")?;
+    let calls = parser.field("calls", true)?;
+    parser.field("errors", false)?;
+    parser.field("time_ns", false)?;
+    parser.token(",")?;
+    parser.key("commands")?;
+    parser.token("[")?;
+    let mut commands = Vec::new();
+    if parser.token("]").is_none() {{
+        loop {{
+            parser.token("{{")?;
+            parser.key("name")?;
+            let name = parser.string()?.to_string();
+            let calls = parser.field("calls", false)?;
+            parser.field("errors", false)?;
+            parser.field("time_ns", false)?;
+            parser.token("}}")?;
+            commands.push((name, calls));
+            if parser.token("]").is_some() {{
+                break;
+            }}
+            parser.token(",")?;
+        }}
+    }}
+    parser.token("}}")?;
+    if !parser.rest.trim_start().is_empty() {{
+        return None;
+    }}
+    Some((calls, commands))
+}}
+"##,
+        per_command = per_command,
+        commands = if gen.per_command_counts { "commands" } else { "_" },
+        profiler_to_json = gen.fn_name("to_json"),
+        profiler_check_against_baseline = gen.fn_name("check_against_baseline"),
+        profiler_call_count = gen.fn_name("call_count"),
+    )
+}
+
+/// Creates the `ProfilerReport` snapshot of the profiler counters.
+pub(crate) fn write_report_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"{serde_guard}
+/// A snapshot of the profiler counters, returned by `{profiler_report}()`.
+///
+/// The metrics which are not enabled in the generator are left as zero or empty.
+#[derive(Clone, Debug, Default, PartialEq)]{serde}
+pub struct ProfilerReport {{
+    /// The number of calls.
+    pub calls: usize,
+    /// The number of errors.
+    pub errors: usize,
+    /// The CPU time spent in the timed commands.
+    pub time_ns: u64,
+    /// The number of calls of each command, in the order of `command_names()`.
+    pub call_counts: Vec<usize>,
+    /// The number of errors of each command, in the order of `command_names()`.
+    pub err_counts: Vec<usize>,
+    /// The CPU time spent in each command, in the order of `command_names()`.
+    pub command_time_ns: Vec<u64>,
+}}
+
+impl ProfilerReport {{
+    /// Returns the difference between this report and an `earlier` one, i.e. what has happened
+    /// in between.
+    pub fn diff(&self, earlier: &ProfilerReport) -> ProfilerReport {{
+        fn diff_all<T: Copy + Default + PartialOrd + ::std::ops::Sub<Output = T>>(a: &[T], b: &[T]) -> Vec<T> {{
+            a.iter()
+                .enumerate()
+                .map(|(i, &x)| {{
+                    let y = b.get(i).copied().unwrap_or_default();
+                    if x > y {{ x - y }} else {{ T::default() }}
+                }})
+                .collect()
+        }}
+        ProfilerReport {{
+            calls: self.calls.saturating_sub(earlier.calls),
+            errors: self.errors.saturating_sub(earlier.errors),
+            time_ns: self.time_ns.saturating_sub(earlier.time_ns),
+            call_counts: diff_all(&self.call_counts, &earlier.call_counts),
+            err_counts: diff_all(&self.err_counts, &earlier.err_counts),
+            command_time_ns: diff_all(&self.command_time_ns, &earlier.command_time_ns),
+        }}
+    }}
+
+    /// Adds the counts and the times of `other` to this report, e.g. to sum the reports of several
+    /// processes or contexts.
+    pub fn merge(&mut self, other: &ProfilerReport) {{
+        fn merge_all<T: Copy + Default + ::std::ops::AddAssign>(a: &mut Vec<T>, b: &[T]) {{
+            if a.len() < b.len() {{
+                a.resize(b.len(), T::default());
+            }}
+            for (x, &y) in a.iter_mut().zip(b) {{
+                *x += y;
+            }}
+        }}
+        self.calls += other.calls;
+        self.errors += other.errors;
+        self.time_ns += other.time_ns;
+        merge_all(&mut self.call_counts, &other.call_counts);
+        merge_all(&mut self.err_counts, &other.err_counts);
+        merge_all(&mut self.command_time_ns, &other.command_time_ns);
+    }}
+}}
+
+/// Returns a snapshot of the profiler counters.
+pub fn {profiler_report}() -> ProfilerReport {{
+    ProfilerReport {{
+        calls: {profiler_call_count}(),
+        errors: {profiler_err_count}(),
+        time_ns: {time_ns},
+        call_counts: {call_counts},
+        err_counts: {err_counts},
+        command_time_ns: {command_time_ns},
+    }}
+}}"##,
+        serde = gen.serde_attr(),
+        serde_guard = if gen.serde {
+            "
+#[cfg(not(feature = \"serde\"))]
+compile_error!(\"the GL bindings are generated with `with_serde(true)`: enable the `serde` feature, which adds the `serde` dependency with its `derive` feature\");
+"
+        } else {
+            ""
+        },
+        time_ns = if gen.cpu_timing { format!("{}()", gen.fn_name("time_ns")) } else { "0".to_string() },
+        call_counts = if gen.per_command_counts {
+            "CALL_COUNTS.iter().map(|c| c.load(::std::sync::atomic::Ordering::SeqCst)).collect()"
+        } else {
+            "Vec::new()"
+        },
+        err_counts = if gen.per_command_counts {
+            "ERR_COUNTS.iter().map(|c| c.load(::std::sync::atomic::Ordering::SeqCst)).collect()"
+        } else {
+            "Vec::new()"
+        },
+        command_time_ns = if gen.per_command_counts && gen.cpu_timing {
+            "COMMAND_TIME_NS.iter().map(|t| t.load(::std::sync::atomic::Ordering::SeqCst)).collect()"
+        } else {
+            "Vec::new()"
+        },
+        profiler_report = gen.fn_name("report"),
+        profiler_call_count = gen.fn_name("call_count"),
+        profiler_err_count = gen.fn_name("err_count"),
+    )
+}
+
+/// Creates the `ProfilerSnapshot`, a cheap capture of the totals to measure the calls and errors
+/// made since.
+pub(crate) fn write_snapshot_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+/// The call and error totals at one point, returned by `{profiler_snapshot}()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]{serde}
+pub struct ProfilerSnapshot {{
+    calls: usize,
+    errors: usize,
+}}
+
+/// Captures the call and error totals, to measure what happens next with
+/// `{profiler_calls_since}` and `{profiler_errors_since}` without resetting the profiler.
+pub fn {profiler_snapshot}() -> ProfilerSnapshot {{
+    ProfilerSnapshot {{
+        calls: {profiler_call_count}(),
+        errors: {profiler_err_count}(),
+    }}
+}}
+
+/// Returns the number of calls made since the snapshot was taken.
+pub fn {profiler_calls_since}(snapshot: &ProfilerSnapshot) -> usize {{
+    {profiler_call_count}().saturating_sub(snapshot.calls)
+}}
+
+/// Returns the number of errors since the snapshot was taken.
+pub fn {profiler_errors_since}(snapshot: &ProfilerSnapshot) -> usize {{
+    {profiler_err_count}().saturating_sub(snapshot.errors)
+}}"##,
+        serde = gen.serde_attr(),
+        profiler_snapshot = gen.fn_name("snapshot"),
+        profiler_calls_since = gen.fn_name("calls_since"),
+        profiler_errors_since = gen.fn_name("errors_since"),
+        profiler_call_count = gen.fn_name("call_count"),
+        profiler_err_count = gen.fn_name("err_count"),
+    )
+}
+
+/// Creates `profiler_print_table`, which prints the counters as a table. There is one row per
+/// command if the per-command counters are enabled, and the average time column is only there if
+/// timing is enabled.
+pub(crate) fn write_table_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let rows = if gen.per_command_counts {
+        format!(
+            "let mut rows: Vec<(&str, usize, usize, u64)> = (0..COMMAND_NAMES.len())
+        .map(|i| (
+            COMMAND_NAMES[i],
+            CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst),
+            ERR_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst),
+            {time},
+        ))
+        .filter(|&(_, calls, errors, _)| calls > 0 || errors > 0)
+        .collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));",
+            time = if gen.cpu_timing {
+                "COMMAND_TIME_NS[i].load(::std::sync::atomic::Ordering::SeqCst)"
+            } else {
+                "0"
+            }
+        )
+    } else {
+        format!(
+            "let rows = [(\"(all commands)\", {call_count}(), {err_count}(), {time})];",
+            call_count = gen.fn_name("call_count"),
+            err_count = gen.fn_name("err_count"),
+            time = if gen.cpu_timing { format!("{}()", gen.fn_name("time_ns")) } else { "0".to_string() }
+        )
+    };
+    let (time_header, time_column) = if gen.cpu_timing {
+        (
+            r#"table.push_str(&format!(" {:>12}", "avg ns"));"#,
+            r#"table.push_str(&format!(" {:>12}", if calls > 0 { time_ns / calls as u64 } else { 0 }));"#,
+        )
+    } else {
+        ("", "")
+    };
+
+    writeln!(
+        dest,
+        r##"
+/// Returns the profiler counters formatted as a table, sorted by the number of calls.
+#[allow(unused_variables)]
+pub fn {profiler_table}() -> String {{
+    {rows}
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max("command".len());
+    let mut table = format!("{{:<width$}} {{:>10}} {{:>10}}", "command", "calls", "errors", width = width);
+    {time_header}
+    table.push('\n');
+    for &(name, calls, errors, time_ns) in rows.iter() {{
+        table.push_str(&format!("{{:<width$}} {{:>10}} {{:>10}}", name, calls, errors, width = width));
+        {time_column}
+        table.push('\n');
+    }}
+    table
+}}
+
+/// Prints the table returned by `{profiler_table}()`.
+pub fn {profiler_print_table}() {{
+    print!("{{}}", {profiler_table}());
+}}"##,
+        rows = rows,
+        time_header = time_header,
+        time_column = time_column,
+        profiler_table = gen.fn_name("table"),
+        profiler_print_table = gen.fn_name("print_table"),
+    )
+}
+
+/// Creates the function which formats the counters for Prometheus.
+pub(crate) fn write_prometheus_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    let mut metrics = vec![
+        ("gl_calls_total", "The number of GL calls.", format!("{}()", gen.fn_name("call_count"))),
+        ("gl_errors_total", "The number of GL errors.", format!("{}()", gen.fn_name("err_count"))),
+    ];
+    if gen.cpu_timing {
+        metrics.push((
+            "gl_cpu_seconds_total",
+            "The CPU time spent in GL calls.",
+            format!("{}() as f64 / 1e9", gen.fn_name("time_ns")),
+        ));
+    }
+    if gen.has_return_checks() {
+        metrics.push((
+            "gl_return_failures_total",
+            "The number of failures reported by return values.",
+            format!("{}()", gen.fn_name("return_err_count")),
+        ));
+    }
+    let mut lines: Vec<String> = metrics
+        .into_iter()
+        .map(|(name, help, value)| {
+            format!(
+                r##"out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} counter\n{name} {{}}\n", {value}));"##,
+                name = name,
+                help = help,
+                value = value
+            )
+        })
+        .collect();
+
+    if gen.per_command_counts {
+        let mut series = vec![
+            ("gl_command_calls_total", "The number of GL calls per command.", "CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst)"),
+            ("gl_command_errors_total", "The number of GL errors per command.", "ERR_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst)"),
+        ];
+        if gen.cpu_timing {
+            series.push((
+                "gl_command_cpu_seconds_total",
+                "The CPU time spent in GL calls per command.",
+                "COMMAND_TIME_NS[i].load(::std::sync::atomic::Ordering::SeqCst) as f64 / 1e9",
+            ));
+        }
+        lines.push(
+            "let called: Vec<usize> = (0..COMMAND_NAMES.len())
+        .filter(|&i| CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) > 0 || ERR_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst) > 0)
+        .collect();"
+                .to_string(),
+        );
+        for (name, help, value) in series {
+            lines.push(format!(
+                r##"out.push_str("# HELP {name} {help}\n# TYPE {name} counter\n");
+    for &i in called.iter() {{
+        out.push_str(&format!("{name}{{{{command=\"{{}}\"}}}} {{}}\n", COMMAND_NAMES[i], {value}));
+    }}"##,
+                name = name,
+                help = help,
+                value = value
+            ));
+        }
+    }
+
+    writeln!(
+        dest,
+        r##"
+/// Returns the profiler counters in the Prometheus text exposition format, with a series per
+/// command when the per-command counters are enabled.
+pub fn {profiler_prometheus}() -> String {{
+    let mut out = String::new();
+    {lines}
+    out
+}}"##,
+        lines = lines.join("\n    "),
+        profiler_prometheus = gen.fn_name("prometheus"),
+    )
+}
@@ -0,0 +1,291 @@
+//! Writers of the CPU and GPU timing helpers and the per-command counters.
+
+use gl_generator::Registry;
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+use crate::util::{gen_counter_static, gen_counter_static_init};
+
+/// Creates the time accumulator used by the commands selected for CPU timing.
+pub(crate) fn write_timing_helper<W>(gen: &ProfilingStructGenerator, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(dest, "{}", gen_counter_static(gen, "TIME_NS", "u64", None))?;
+    writeln!(dest, "{}", gen_counter_static(gen, "TIMER_OVERHEAD_NS", "u64", None))?;
+    write!(
+        dest,
+        r##"
+pub fn {profiler_time_ns}() -> u64 {{
+    TIME_NS.load(::std::sync::atomic::Ordering::SeqCst)
+}}
+
+/// Returns `{profiler_time_ns}()` in microseconds.
+pub fn {profiler_time_us}() -> f64 {{
+    {profiler_time_ns}() as f64 / 1e3
+}}
+
+/// Returns `{profiler_time_ns}()` in milliseconds.
+pub fn {profiler_time_ms}() -> f64 {{
+    {profiler_time_ns}() as f64 / 1e6
+}}
+
+/// Measures the cost of timing a call, and subtracts it from the time of each following call.
+/// Returns the measured cost, in nanoseconds.
+///
+/// The cost is the fastest of many measurements, so that it is not overestimated when the thread
+/// is preempted.
+pub fn {profiler_calibrate_timer}() -> u64 {{
+    let overhead = (0..1000)
+        .map(|_| ::std::time::Instant::now().elapsed().as_nanos() as u64)
+        .min()
+        .unwrap_or(0);
+    TIMER_OVERHEAD_NS.store(overhead, ::std::sync::atomic::Ordering::SeqCst);
+    overhead
+}}
+
+/// Returns the cost of timing a call measured by `{profiler_calibrate_timer}`, or zero if the timer
+/// has not been calibrated.
+pub fn {profiler_timer_overhead_ns}() -> u64 {{
+    TIMER_OVERHEAD_NS.load(::std::sync::atomic::Ordering::SeqCst)
+}}
+
+fn elapsed_ns(start: ::std::time::Instant) -> u64 {{
+    (start.elapsed().as_nanos() as u64).saturating_sub(TIMER_OVERHEAD_NS.load(::std::sync::atomic::Ordering::SeqCst))
+}}
+
+#[allow(dead_code)]
+fn add_time(start: ::std::time::Instant) {{
+    TIME_NS.fetch_add(elapsed_ns(start){weight}, ::std::sync::atomic::Ordering::SeqCst);
+}}
+    "##,
+        weight = if gen.sampling > 1 { " * SAMPLE_WEIGHT as u64" } else { "" },
+        profiler_time_ns = gen.fn_name("time_ns"),
+        profiler_time_us = gen.fn_name("time_us"),
+        profiler_time_ms = gen.fn_name("time_ms"),
+        profiler_calibrate_timer = gen.fn_name("calibrate_timer"),
+        profiler_timer_overhead_ns = gen.fn_name("timer_overhead_ns"),
+    )
+}
+
+/// Creates the fence latency accumulators and the queue of the pending fences.
+pub(crate) fn write_gpu_fence_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+{fence_latency_ns}
+
+/// The fences which have not been seen completed yet, oldest first, of all the contexts and instances, with the index of the command
+/// they follow and the time they were inserted.
+static PENDING_FENCES: ::std::sync::Mutex<Vec<(usize, usize, ::std::time::Instant)>> = ::std::sync::Mutex::new(Vec::new());
+
+/// No more fences are inserted while this many are pending, in case the fences are never collected.
+const MAX_PENDING_FENCES: usize = 1024;
+
+/// Returns the fence latency of all the fenced commands: the CPU wall-clock time from the insertion
+/// of each fence until `{profiler_collect_fence_latencies}` saw it signalled.
+///
+/// This is not the GPU execution time: it includes the time the commands were queued and the
+/// time until the poll. The fences of all the contexts and instances are pooled.
+pub fn {profiler_fence_latency_ns}() -> u64 {{
+    FENCE_LATENCY_NS.iter().map(|t| t.load(::std::sync::atomic::Ordering::SeqCst)).sum()
+}}
+
+/// Returns the fence latency of the named command (e.g. `"glDrawArrays"`), or `None` if there is
+/// no such command.
+pub fn {profiler_fence_latency_ns_for}(name: &str) -> Option<u64> {{
+    command_index(name).map(|i| FENCE_LATENCY_NS[i].load(::std::sync::atomic::Ordering::SeqCst))
+}}"##,
+        fence_latency_ns = gen_counter_static(gen, "FENCE_LATENCY_NS", "u64", Some(registry.cmds.len())),
+        profiler_collect_fence_latencies = gen.fn_name("collect_fence_latencies"),
+        profiler_fence_latency_ns = gen.fn_name("fence_latency_ns"),
+        profiler_fence_latency_ns_for = gen.fn_name("fence_latency_ns_for"),
+    )
+}
+
+/// Creates the per-command counters, indexed in the order of `command_names()`.
+pub(crate) fn write_per_command_helper<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+{
+    writeln!(
+        dest,
+        r##"
+{call_counts}
+{err_counts}
+
+/// Returns the number of calls of each command which has been called since the last reset.
+pub fn {profiler_call_counts}() -> Vec<(&'static str, usize)> {{
+    COMMAND_NAMES
+        .iter()
+        .zip(CALL_COUNTS.iter())
+        .map(|(&name, count)| (name, count.load(::std::sync::atomic::Ordering::SeqCst)))
+        .filter(|&(_, count)| count > 0)
+        .collect()
+}}
+
+/// Writes the number of calls of each command which has been called since the last reset into
+/// `out`, like `{profiler_call_counts}` but without allocating. Returns the number of commands
+/// written, at most `out.len()`.
+pub fn {profiler_call_counts_into}(out: &mut [(&'static str, usize)]) -> usize {{
+    let called = COMMAND_NAMES
+        .iter()
+        .zip(CALL_COUNTS.iter())
+        .map(|(&name, count)| (name, count.load(::std::sync::atomic::Ordering::SeqCst)))
+        .filter(|&(_, count)| count > 0);
+    let mut written = 0;
+    for (slot, entry) in out.iter_mut().zip(called) {{
+        *slot = entry;
+        written += 1;
+    }}
+    written
+}}
+
+/// The indices of the commands, ordered by their names, ignoring the case.
+static COMMANDS_BY_NAME: [usize; {count}] = [{by_name}];
+
+/// Returns the same as `{profiler_call_counts}`, ordered by the command names ignoring the case, so
+/// that the order stays the same as more commands are called.
+pub fn {profiler_call_counts_sorted}() -> Vec<(&'static str, usize)> {{
+    COMMANDS_BY_NAME
+        .iter()
+        .map(|&i| (COMMAND_NAMES[i], CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst)))
+        .filter(|&(_, count)| count > 0)
+        .collect()
+}}
+
+/// Returns the number of calls of the named command (e.g. `"glDrawArrays"`), or `None` if there
+/// is no such command.
+pub fn {profiler_call_count_for}(name: &str) -> Option<usize> {{
+    command_index(name).map(|i| CALL_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst))
+}}
+
+/// Returns the number of errors of the named command (e.g. `"glDrawArrays"`), or `None` if there
+/// is no such command.
+pub fn {profiler_err_count_for}(name: &str) -> Option<usize> {{
+    command_index(name).map(|i| ERR_COUNTS[i].load(::std::sync::atomic::Ordering::SeqCst))
+}}
+
+fn inc_command_call(index: usize) {{
+    CALL_COUNTS[index].fetch_add({weight}, ::std::sync::atomic::Ordering::SeqCst);
+}}
+
+#[allow(dead_code)]
+fn inc_command_err(index: usize) {{
+    ERR_COUNTS[index].fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+}}"##,
+        weight = gen.call_weight(),
+        call_counts = gen_counter_static(gen, "CALL_COUNTS", "usize", Some(registry.cmds.len())),
+        err_counts = gen_counter_static(gen, "ERR_COUNTS", "usize", Some(registry.cmds.len())),
+        count = registry.cmds.len(),
+        by_name = gen_commands_by_name(registry)
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        profiler_call_counts = gen.fn_name("call_counts"),
+        profiler_call_counts_into = gen.fn_name("call_counts_into"),
+        profiler_call_counts_sorted = gen.fn_name("call_counts_sorted"),
+        profiler_call_count_for = gen.fn_name("call_count_for"),
+        profiler_err_count_for = gen.fn_name("err_count_for"),
+    )?;
+
+    if gen.cpu_timing {
+        writeln!(
+            dest,
+            r##"
+{command_time_ns}
+
+/// Returns the CPU time spent in the named command (e.g. `"glDrawArrays"`), or `None` if there is
+/// no such command.
+pub fn {profiler_time_ns_for}(name: &str) -> Option<u64> {{
+    command_index(name).map(|i| COMMAND_TIME_NS[i].load(::std::sync::atomic::Ordering::SeqCst))
+}}
+
+/// Returns `{profiler_time_ns_for}(name)` in microseconds.
+pub fn {profiler_time_us_for}(name: &str) -> Option<f64> {{
+    {profiler_time_ns_for}(name).map(|ns| ns as f64 / 1e3)
+}}
+
+/// Returns `{profiler_time_ns_for}(name)` in milliseconds.
+pub fn {profiler_time_ms_for}(name: &str) -> Option<f64> {{
+    {profiler_time_ns_for}(name).map(|ns| ns as f64 / 1e6)
+}}
+
+{command_min_ns}
+{command_max_ns}
+
+/// Returns the shortest and the longest CPU time of a single call of the named command, or `None`
+/// if there is no such command or it has not been timed since the last reset.
+pub fn {profiler_time_minmax}(name: &str) -> Option<(u64, u64)> {{
+    let i = command_index(name)?;
+    match COMMAND_MIN_NS[i].load(::std::sync::atomic::Ordering::SeqCst) {{
+        u64::MAX => None,
+        min => Some((min, COMMAND_MAX_NS[i].load(::std::sync::atomic::Ordering::SeqCst))),
+    }}
+}}
+
+{watch_ns}
+
+/// Logs every call of the named command which takes longer than `threshold_ns`, e.g. to catch the
+/// occasional stall of `"glFinish"`, or stops with a threshold of 0. Returns `false` if there is no
+/// such command.
+pub fn {profiler_watch}(name: &str, threshold_ns: u64) -> bool {{
+    match command_index(name) {{
+        Some(i) => {{
+            WATCH_NS[i].store(threshold_ns, ::std::sync::atomic::Ordering::SeqCst);
+            true
+        }}
+        None => false,
+    }}
+}}
+
+#[cold]
+#[inline(never)]
+fn report_watched_call(index: usize, elapsed: u64, threshold: u64) {{
+    println!(
+        "{log_prefix} {{}} took {{:.3}}ms, over the watched {{:.3}}ms",
+        COMMAND_NAMES[index],
+        elapsed as f64 / 1e6,
+        threshold as f64 / 1e6
+    );
+}}
+
+fn add_command_time(index: usize, start: ::std::time::Instant) {{
+    let elapsed = elapsed_ns(start);
+    TIME_NS.fetch_add(elapsed{weight}, ::std::sync::atomic::Ordering::SeqCst);
+    COMMAND_TIME_NS[index].fetch_add(elapsed{weight}, ::std::sync::atomic::Ordering::SeqCst);
+    COMMAND_MIN_NS[index].fetch_min(elapsed, ::std::sync::atomic::Ordering::SeqCst);
+    COMMAND_MAX_NS[index].fetch_max(elapsed, ::std::sync::atomic::Ordering::SeqCst);
+    let threshold = WATCH_NS[index].load(::std::sync::atomic::Ordering::Relaxed);
+    if threshold > 0 && elapsed > threshold {{
+        report_watched_call(index, elapsed, threshold);
+    }}
+}}"##,
+            log_prefix = gen.log_prefix(registry),
+            weight = if gen.sampling > 1 { " * SAMPLE_WEIGHT as u64" } else { "" },
+            watch_ns = gen_counter_static(gen, "WATCH_NS", "u64", Some(registry.cmds.len())),
+            command_time_ns = gen_counter_static(gen, "COMMAND_TIME_NS", "u64", Some(registry.cmds.len())),
+            command_min_ns = gen_counter_static_init(gen, "COMMAND_MIN_NS", "u64", Some(registry.cmds.len()), "u64::MAX"),
+            command_max_ns = gen_counter_static(gen, "COMMAND_MAX_NS", "u64", Some(registry.cmds.len())),
+            profiler_time_ns_for = gen.fn_name("time_ns_for"),
+            profiler_time_us_for = gen.fn_name("time_us_for"),
+            profiler_time_ms_for = gen.fn_name("time_ms_for"),
+            profiler_time_minmax = gen.fn_name("time_minmax"),
+            profiler_watch = gen.fn_name("watch"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns the indices of the commands, ordered by their names ignoring the case.
+pub(crate) fn gen_commands_by_name(registry: &Registry) -> Vec<usize> {
+    let names: Vec<_> = registry.cmds.iter().map(|cmd| &cmd.proto.ident).collect();
+    let mut indices: Vec<_> = (0..names.len()).collect();
+    indices.sort_by_key(|&i| (names[i].to_lowercase(), names[i]));
+    indices
+}
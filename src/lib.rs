@@ -10,11 +10,20 @@ Yet another generator for `gl_generator`, very similar to `DebugStructGenerator`
 
 ### Using the profiler
 
-The generated `gl` module gains 3 additional methods:
+The generated `gl` module gains these additional methods:
 
 - `profiler_reset()` - resets the profiler;
 - `profiler_call_count() -> usize` - returns the number of calls since the last reset (or application start);
 - `profiler_err_count() -> usize` - returns the number of errors since the last reset (or application start);
+- `profiler_is_reset() -> bool` - returns whether all the counters are zero, e.g. at the start of a test;
+- `profiler_first_error() -> Option<(&'static str, u32)>` - returns the command and the code of the first error
+  since the last reset, which is usually the root cause of the errors that follow;
+- `profiler_take_last_error() -> Option<(&'static str, u32)>` - returns and clears the command and the code of
+  the last error;
+- `profiler_call_count_relaxed() -> usize` and `profiler_err_count_relaxed() -> usize` - return the same
+  counts as above with `Relaxed` loads, for cheap sampling, e.g. every frame;
+- `profiler_summary() -> String` - returns the enabled metrics on one line, e.g.
+  `GL: 1234 calls, 2 errors, 3.1ms`, for logging at the end of a frame;
 
 Example usage:
 
@@ -27,6 +36,11 @@ println!("Number of GL calls: {}", gl::profiler_call_count());
 println!("Number of GL errors: {}", gl::profiler_err_count());
 ```
 
+The generator is configured with the `with_*` methods of `ProfilingStructGenerator`, which add
+timing, per-command counters, reports and more. The
+[README](https://github.com/Nercury/gl_generator_profiling_struct-rs/blob/master/README.md)
+describes each of them.
+
 ## Setting up the build script
 
 The build script is very similar to the one used by `gl` crate. Here is the example:
@@ -50,373 +64,149 @@ fn main() {
     ]);
 
     registry.write_bindings(
-        ProfilingStructGenerator,
+        ProfilingStructGenerator::new(),
         &mut file_gl
     ).unwrap();
 }
 ```
 
+The build scripts written for the earlier versions, which pass the unit struct
+`ProfilingStructGenerator`, still work: it is now a constant equal to `ProfilingStructGenerator::new()`.
+
+The generated code needs Rust 1.63 or later, and up to 1.82 with some of the options, e.g. 1.70
+with `with_global_instance`; the README lists them.
+
 */
 
 extern crate gl_generator;
 
-use gl_generator::{Registry, generators};
+mod bindings;
+mod builder;
+mod builtins;
+mod helpers;
+mod util;
 
+pub use builder::ProfilingStructGenerator;
+
+use gl_generator::Registry;
+use std::borrow::Cow;
 use std::io;
 
-#[allow(missing_copy_implementations)]
-pub struct ProfilingStructGenerator;
+use crate::bindings::mock::{write_mock_fns, write_mock_value_trait};
+use crate::bindings::structs::{write_bare_struct, write_global_instance, write_impl, write_struct};
+use crate::bindings::{write_api_info, write_command_names, write_enum_names, write_enums, write_fnptr_struct_def, write_header, write_panicking_fns, write_type_aliases};
+use crate::helpers::write_profiler;
+use crate::util::{with_command_allowlist, with_types_path};
 
 impl gl_generator::Generator for ProfilingStructGenerator {
     fn write<W>(&self, registry: &Registry, dest: &mut W) -> io::Result<()>
         where
             W: io::Write,
     {
-        write_helper(dest)?;
-        write_header(dest)?;
-        write_type_aliases(registry, dest)?;
-        write_enums(registry, dest)?;
-        write_fnptr_struct_def(dest)?;
-        write_panicking_fns(registry, dest)?;
-        write_struct(registry, dest)?;
-        write_impl(registry, dest)?;
-        Ok(())
-    }
-}
+        self.validate()?;
+        let (registry, types_prefix) = self.prepare_registry(registry)?;
 
-/// Creates a `__gl_imports` module which contains all the external symbols that we need for the
-///  bindings.
-fn write_helper<W>(dest: &mut W) -> io::Result<()>
-    where
-        W: io::Write,
-{
-    writeln!(
-        dest,
-        "{}",
-        r##"
-static CALL_COUNT: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
-static ERR_COUNT: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
-
-pub fn profiler_reset() {
-    CALL_COUNT.store(0, ::std::sync::atomic::Ordering::SeqCst);
-    ERR_COUNT.store(0, ::std::sync::atomic::Ordering::SeqCst);
-}
-
-pub fn profiler_call_count() -> usize {
-    CALL_COUNT.load(::std::sync::atomic::Ordering::SeqCst)
-}
-
-fn inc_call() {
-    CALL_COUNT.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
-}
-
-pub fn profiler_err_count() -> usize {
-    ERR_COUNT.load(::std::sync::atomic::Ordering::SeqCst)
-}
-
-fn inc_err() {
-    ERR_COUNT.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
-}
-
-fn gl_error_to_str(error: u32) -> &'static str {
-    match error {
-        self::NO_ERROR => {
-            "NO_ERROR = No error has been recorded.
-                        The value of this \
-                      symbolic constant is guaranteed to be 0."
-        }
-        self::INVALID_ENUM => {
-            "INVALID_ENUM = An unacceptable value is specified for an enumerated argument.
-                        \
-                      The offending command is ignored
-                        and has no other \
-                      side effect than to set the error flag."
-        }
-        self::INVALID_VALUE => {
-            "INVALID_VALUE = A numeric argument is out of range.
-                        The offending command is ignored
-                        and has no other side effect than to set the error flag."
-        }
-        self::INVALID_OPERATION => {
-            "INVALID_OPERATION = The specified operation is not allowed in the current \
-                      state.
-                        The offending command is ignored
-                        \
-                      and has no other side effect than to set the error flag."
+        if let Some(ref module) = self.module {
+            writeln!(dest, "pub mod {} {{", module)?;
         }
-        self::INVALID_FRAMEBUFFER_OPERATION => {
-            "INVALID_FRAMEBUFFER_OPERATION = The command is trying to render to or read \
-                      from the framebuffer
-                        while the currently bound \
-                      framebuffer is not framebuffer
-                        complete (i.e. the \
-                      return value from
-                        glCheckFramebufferStatus
-                        \
-                      is not GL_FRAMEBUFFER_COMPLETE).
-                        The offending \
-                      command is ignored
-                        and has no other side effect than \
-                      to set the error flag."
-        }
-        self::OUT_OF_MEMORY => {
-            "OUT_OF_MEMORY = There is not enough memory left to execute the command.
-                        The state of the GL is undefined,
-                        except for the state of the error flags,
-                        after this error is recorded."
+        write_header(dest)?;
+        write_types_part(self, &registry, &types_prefix, dest)?;
+        write_impl_part(self, &registry, dest)?;
+        if self.module.is_some() {
+            writeln!(dest, "}}")?;
         }
-        _ => "Unknown error",
+        Ok(())
     }
 }
-    "##
-    )
-}
-
-/// Creates a `__gl_imports` module which contains all the external symbols that we need for the
-///  bindings.
-fn write_header<W>(dest: &mut W) -> io::Result<()>
-    where
-        W: io::Write,
-{
-    writeln!(
-        dest,
-        r#"
-        mod __gl_imports {{
-            pub use std::mem;
-            pub use std::marker::Send;
-            pub use std::os::raw;
-        }}
-    "#
-    )
-}
-
-/// Creates a `types` module which contains all the type aliases.
-///
-/// See also `generators::gen_types`.
-fn write_type_aliases<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
-    where
-        W: io::Write,
-{
-    writeln!(
-        dest,
-        r#"
-        pub mod types {{
-            #![allow(non_camel_case_types, non_snake_case, dead_code, missing_copy_implementations)]
-    "#
-    )?;
 
-    generators::gen_types(registry.api, dest)?;
-
-    writeln!(dest, "}}")
-}
+impl ProfilingStructGenerator {
+    /// Writes the bindings like `Registry::write_bindings`, but split in two: the `types` module
+    /// and the enums to `types_dest`, and everything else to `impl_dest`, so that the two can be
+    /// compiled as separate modules.
+    ///
+    /// The code in `impl_dest` expects the items of `types_dest` in scope:
+    ///
+    /// ```rust,no_run,ignore
+    /// pub mod gl_types {
+    ///     include!(concat!(env!("OUT_DIR"), "/gl_types.rs"));
+    /// }
+    /// pub mod gl {
+    ///     pub use super::gl_types::*;
+    ///     include!(concat!(env!("OUT_DIR"), "/gl.rs"));
+    /// }
+    /// ```
+    ///
+    /// Returns an error if a module was set with `with_module`, since the two parts are placed in
+    /// modules by the includer.
+    pub fn write_split<T, I>(&self, registry: &Registry, types_dest: &mut T, impl_dest: &mut I) -> io::Result<()>
+        where
+            T: io::Write,
+            I: io::Write,
+    {
+        self.validate()?;
+        if self.module.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "`with_module` can not be used with `write_split`: the two parts are placed in modules by the includer",
+            ));
+        }
+        let (registry, types_prefix) = self.prepare_registry(registry)?;
 
-/// Creates all the `<enum>` elements at the root of the bindings.
-fn write_enums<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
-    where
-        W: io::Write,
-{
-    for enm in &registry.enums {
-        generators::gen_enum_item(enm, "types::", dest)?;
+        write_header(types_dest)?;
+        write_types_part(self, &registry, &types_prefix, types_dest)?;
+        write_header(impl_dest)?;
+        write_impl_part(self, &registry, impl_dest)
     }
 
-    Ok(())
-}
-
-/// Creates a `FnPtr` structure which contains the store for a single binding.
-fn write_fnptr_struct_def<W>(dest: &mut W) -> io::Result<()>
-    where
-        W: io::Write,
-{
-    writeln!(
-        dest,
-        "
-        #[allow(dead_code, missing_copy_implementations)]
-        #[derive(Clone)]
-        pub struct FnPtr {{
-            /// The function pointer that will be used when calling the function.
-            f: *const __gl_imports::raw::c_void,
-            /// True if the pointer points to a real function, false if points to a `panic!` fn.
-            is_loaded: bool,
-        }}
-        impl FnPtr {{
-            /// Creates a `FnPtr` from a load attempt.
-            fn new(ptr: *const __gl_imports::raw::c_void) -> FnPtr {{
-                if ptr.is_null() {{
-                    FnPtr {{
-                        f: missing_fn_panic as *const __gl_imports::raw::c_void,
-                        is_loaded: false
-                    }}
-                }} else {{
-                    FnPtr {{ f: ptr, is_loaded: true }}
-                }}
-            }}
-            /// Returns `true` if the function has been successfully loaded.
-            ///
-            /// If it returns `false`, calling the corresponding function will fail.
-            #[inline]
-            #[allow(dead_code)]
-            pub fn is_loaded(&self) -> bool {{
-                self.is_loaded
-            }}
-        }}
-    "
-    )
-}
-
-/// Creates a `panicking` module which contains one function per GL command.
-///
-/// These functions are the mocks that are called if the real function could not be loaded.
-fn write_panicking_fns<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
-    where
-        W: io::Write,
-{
-    writeln!(
-        dest,
-        "#[inline(never)]
-        fn missing_fn_panic() -> ! {{
-            panic!(\"{api} function was not loaded\")
-        }}",
-        api = registry.api
-    )
+    /// Returns the registry with the commands and the types configured with
+    /// `with_command_allowlist` and `with_types_path`, and the prefix of the types.
+    fn prepare_registry<'a>(&self, registry: &'a Registry) -> io::Result<(Cow<'a, Registry>, String)> {
+        let registry = match self.command_allowlist {
+            Some(ref names) => Cow::Owned(with_command_allowlist(registry, names)?),
+            None => Cow::Borrowed(registry),
+        };
+        Ok(match self.types_path {
+            Some(ref path) => (Cow::Owned(with_types_path(&registry, path)), format!("{}::", path)),
+            None => (registry, "types::".to_string()),
+        })
+    }
 }
 
-/// Creates a structure which stores all the `FnPtr` of the bindings.
-///
-/// The name of the struct corresponds to the namespace.
-fn write_struct<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+/// Creates the `types` module, if enabled, and the enums.
+fn write_types_part<W>(gen: &ProfilingStructGenerator, registry: &Registry, types_prefix: &str, dest: &mut W) -> io::Result<()>
     where
         W: io::Write,
 {
-    writeln!(
-        dest,
-        "
-        #[allow(non_camel_case_types, non_snake_case, dead_code)]
-        #[derive(Clone)]
-        pub struct {api} {{",
-        api = generators::gen_struct_name(registry.api)
-    )?;
-
-    for cmd in &registry.cmds {
-        if let Some(v) = registry.aliases.get(&cmd.proto.ident) {
-            writeln!(dest, "/// Fallbacks: {}", v.join(", "))?;
-        }
-        writeln!(dest, "pub {name}: FnPtr,", name = cmd.proto.ident)?;
+    if gen.types {
+        write_type_aliases(registry, dest)?;
     }
-    writeln!(dest, "_priv: ()")?;
-
-    writeln!(dest, "}}")
+    write_enums(registry, types_prefix, dest)
 }
 
-/// Creates the `impl` of the structure created by `write_struct`.
-fn write_impl<W>(registry: &Registry, dest: &mut W) -> io::Result<()>
+/// Creates everything but the types and the enums: the profiler, the struct and its `impl`.
+fn write_impl_part<W>(gen: &ProfilingStructGenerator, registry: &Registry, dest: &mut W) -> io::Result<()>
     where
         W: io::Write,
 {
-    writeln!(dest,
-                  "impl {api} {{
-            /// Load each OpenGL symbol using a custom load function. This allows for the
-            /// use of functions like `glfwGetProcAddress` or `SDL_GL_GetProcAddress`.
-            ///
-            /// ~~~ignore
-            /// let gl = Gl::load_with(|s| glfw.get_proc_address(s));
-            /// ~~~
-            #[allow(dead_code, unused_variables)]
-            pub fn load_with<F>(mut loadfn: F) -> {api} where F: FnMut(&'static str) -> *const __gl_imports::raw::c_void {{
-                #[inline(never)]
-                fn do_metaloadfn(loadfn: &mut dyn FnMut(&'static str) -> *const __gl_imports::raw::c_void,
-                                 symbol: &'static str,
-                                 symbols: &[&'static str])
-                                 -> *const __gl_imports::raw::c_void {{
-                    let mut ptr = loadfn(symbol);
-                    if ptr.is_null() {{
-                        for &sym in symbols {{
-                            ptr = loadfn(sym);
-                            if !ptr.is_null() {{ break; }}
-                        }}
-                    }}
-                    ptr
-                }}
-                let mut metaloadfn = |symbol: &'static str, symbols: &[&'static str]| {{
-                    do_metaloadfn(&mut loadfn, symbol, symbols)
-                }};
-                {api} {{",
-                  api = generators::gen_struct_name(registry.api))?;
-
-    for cmd in &registry.cmds {
-        writeln!(
-            dest,
-            "{name}: FnPtr::new(metaloadfn(\"{symbol}\", &[{fallbacks}])),",
-            name = cmd.proto.ident,
-            symbol = generators::gen_symbol_name(registry.api, &cmd.proto.ident),
-            fallbacks = match registry.aliases.get(&cmd.proto.ident) {
-                Some(fbs) => fbs.iter()
-                    .map(|name| format!("\"{}\"", generators::gen_symbol_name(registry.api, &name)))
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                None => format!(""),
-            },
-        )?
+    write_profiler(gen, registry, dest)?;
+    write_enum_names(gen, registry, dest)?;
+    write_api_info(gen, registry, dest)?;
+    write_command_names(registry, dest)?;
+    write_fnptr_struct_def(gen, dest)?;
+    write_panicking_fns(registry, dest)?;
+    if gen.mock || gen.dry_run {
+        write_mock_value_trait(dest)?;
     }
-    writeln!(dest, "_priv: ()")?;
-
-    writeln!(
-        dest,
-        "}}
-        }}"
-    )?;
-
-    for cmd in &registry.cmds {
-        let idents = generators::gen_parameters(cmd, true, false);
-        let typed_params = generators::gen_parameters(cmd, false, true);
-        let println = format!(
-            "println!(\"[OpenGL] {}({})\" {});",
-            cmd.proto.ident,
-            (0..idents.len())
-                .map(|_| "{:?}".to_string())
-                .collect::<Vec<_>>()
-                .join(", "),
-            idents
-                .iter()
-                .zip(typed_params.iter())
-                .map(|(name, ty)| if ty.contains("GLDEBUGPROC") {
-                    format!(", \"<callback>\"")
-                } else {
-                    format!(", {}", name)
-                })
-                .collect::<Vec<_>>()
-                .concat()
-        );
-
-        writeln!(dest,
-                      "#[allow(non_snake_case, unused_variables, dead_code)]
-            #[inline] pub unsafe fn {name}(&self, {params}) -> {return_suffix} {{ \
-                let r = __gl_imports::mem::transmute::<_, extern \"system\" fn({typed_params}) -> {return_suffix}>\
-                    (self.{name}.f)({idents});
-                {print_err}
-                r
-            }}",
-                      name = cmd.proto.ident,
-                      params = generators::gen_parameters(cmd, true, true).join(", "),
-                      typed_params = typed_params.join(", "),
-                      return_suffix = cmd.proto.ty,
-                      idents = idents.join(", "),
-                      print_err = if cmd.proto.ident != "GetError" &&
-                          registry
-                              .cmds
-                              .iter()
-                              .find(|cmd| cmd.proto.ident == "GetError")
-                              .is_some() {
-                          format!(r#"match __gl_imports::mem::transmute::<_, extern "system" fn() -> u32>
-                    (self.GetError.f)() {{ 0 => inc_call(), r => {{ inc_err(); {println} println!("[OpenGL] ^ GL error triggered: {{}}, {{}}", r, gl_error_to_str(r))}} }}"#, println = println)
-                      } else {
-                          format!("")
-                      })?
+    if gen.mock {
+        write_mock_fns(registry, dest)?;
     }
-
-    writeln!(
-        dest,
-        "}}
-        unsafe impl __gl_imports::Send for {api} {{}}",
-        api = generators::gen_struct_name(registry.api)
-    )
-}
\ No newline at end of file
+    write_struct(gen, registry, dest)?;
+    write_impl(gen, registry, dest)?;
+    if let Some(ref name) = gen.bare_struct {
+        write_bare_struct(gen, name, registry, dest)?;
+    }
+    if gen.global_instance {
+        write_global_instance(gen, registry, dest)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,250 @@
+//! Small helpers shared by the writers.
+
+use gl_generator::{Api, Binding, Cmd, Profile, Registry, generators};
+use std::io;
+
+use crate::builder::ProfilingStructGenerator;
+
+/// Returns `true` if the name can be used as a Rust identifier (keywords are not checked).
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// Returns `true` if the command matches any of the patterns accepted by the builder methods.
+pub(crate) fn matches_any(registry: &Registry, cmd: &Cmd, patterns: &[String]) -> bool {
+    let symbol = generators::gen_symbol_name(registry.api, &cmd.proto.ident);
+    patterns.iter().any(|pattern| {
+        [symbol.as_str(), cmd.proto.ident.as_str()].iter().any(|name| {
+            match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            }
+        })
+    })
+}
+
+/// Generates the URL of the Khronos reference page of a command, if the API has reference pages.
+pub(crate) fn gen_doc_link(api: Api, symbol: &str) -> Option<String> {
+    match api {
+        Api::Gl | Api::GlCore => Some(format!(
+            "https://registry.khronos.org/OpenGL-Refpages/gl4/html/{}.xhtml",
+            symbol
+        )),
+        Api::Gles2 => Some(format!(
+            "https://registry.khronos.org/OpenGL-Refpages/es3/html/{}.xhtml",
+            symbol
+        )),
+        Api::Gles1 => Some(format!(
+            "https://registry.khronos.org/OpenGL-Refpages/es1.1/xhtml/{}.xml",
+            symbol
+        )),
+        Api::Egl => Some(format!(
+            "https://registry.khronos.org/EGL/sdk/docs/man/html/{}.xhtml",
+            symbol
+        )),
+        Api::Glx | Api::Wgl | Api::Glsc2 => None,
+    }
+}
+
+/// Generates the name of a profile, as used in the registry.
+pub(crate) fn gen_profile_name(profile: Profile) -> &'static str {
+    match profile {
+        Profile::Core => "core",
+        Profile::Compatibility => "compatibility",
+    }
+}
+
+/// Returns the group of the enums accepted by the parameter, if it is a `GLenum`.
+pub(crate) fn enum_group<'a>(registry: &'a Registry, binding: &Binding) -> Option<&'a gl_generator::Group> {
+    if !binding.ty.ends_with("GLenum") || binding.ty.contains('*') {
+        return None;
+    }
+    binding.group.as_ref().and_then(|group| registry.groups.get(group))
+}
+
+/// Returns `true` if the registry contains the `GetError` command used for error checking.
+pub(crate) fn has_get_error(registry: &Registry) -> bool {
+    registry.cmds.iter().any(|cmd| cmd.proto.ident == "GetError")
+}
+
+/// Generates the declaration of the `name` counter of type `usize` or `u64`, or of an array of
+/// `len` counters.
+///
+/// The counters are atomics, or `LocalCounter`s in the single-threaded mode.
+pub(crate) fn gen_counter_static(gen: &ProfilingStructGenerator, name: &str, ty: &str, len: Option<usize>) -> String {
+    gen_counter_static_init(gen, name, ty, len, "0")
+}
+
+/// Generates the declaration of counters like `gen_counter_static`, which start at `init`.
+pub(crate) fn gen_counter_static_init(gen: &ProfilingStructGenerator, name: &str, ty: &str, len: Option<usize>, init: &str) -> String {
+    if gen.single_thread {
+        return match len {
+            Some(len) => format!(
+                "thread_local! {{
+    static {name}_CELLS: [::std::cell::Cell<{ty}>; {len}] = const {{
+        #[allow(clippy::declare_interior_mutable_const)]
+        const INIT: ::std::cell::Cell<{ty}> = ::std::cell::Cell::new({init});
+        [INIT; {len}]
+    }};
+}}
+static {name}: [LocalCounter<{ty}, {len}>; {len}] = local_counters(&{name}_CELLS);",
+                name = name,
+                ty = ty,
+                len = len,
+                init = init
+            ),
+            None => format!(
+                "thread_local! {{
+    static {name}_CELLS: [::std::cell::Cell<{ty}>; 1] = const {{ [::std::cell::Cell::new({init})] }};
+}}
+static {name}: LocalCounter<{ty}, 1> = LocalCounter {{ cells: &{name}_CELLS, index: 0 }};",
+                name = name,
+                ty = ty,
+                init = init
+            ),
+        };
+    }
+
+    let atomic = if ty == "u64" { "AtomicU64" } else { "AtomicUsize" };
+    match len {
+        Some(len) => format!(
+            "static {name}: [::std::sync::atomic::{atomic}; {len}] = {{
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: ::std::sync::atomic::{atomic} = ::std::sync::atomic::{atomic}::new({init});
+    [INIT; {len}]
+}};",
+            name = name,
+            atomic = atomic,
+            len = len,
+            init = init
+        ),
+        None => format!(
+            "static {name}: ::std::sync::atomic::{atomic} = ::std::sync::atomic::{atomic}::new({init});",
+            name = name,
+            atomic = atomic,
+            init = init
+        ),
+    }
+}
+
+/// Returns a copy of the registry whose commands refer to the type aliases through `path` instead
+/// of the `types` module.
+pub(crate) fn with_types_path(registry: &Registry, path: &str) -> Registry {
+    let prefix = format!("{}::", path);
+    let rewrite = |binding: &Binding| Binding {
+        ty: binding.ty.replace("types::", &prefix).into(),
+        ..binding.clone()
+    };
+    let mut rewritten = registry.clone();
+    rewritten.cmds = registry
+        .cmds
+        .iter()
+        .map(|cmd| Cmd {
+            proto: rewrite(&cmd.proto),
+            params: cmd.params.iter().map(rewrite).collect(),
+            ..cmd.clone()
+        })
+        .collect();
+    rewritten
+}
+
+/// Returns a copy of the registry which only contains the named commands, and `GetError`.
+///
+/// Fails if a name is not the symbol of a command of the registry.
+pub(crate) fn with_command_allowlist(registry: &Registry, names: &[String]) -> io::Result<Registry> {
+    let symbol = |cmd: &Cmd| generators::gen_symbol_name(registry.api, &cmd.proto.ident);
+    if let Some(unknown) = names.iter().find(|&name| !registry.cmds.iter().any(|cmd| symbol(cmd) == *name)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`with_command_allowlist`: there is no command `{}` in the registry", unknown),
+        ));
+    }
+    let mut allowed = registry.clone();
+    allowed.cmds = registry
+        .cmds
+        .iter()
+        .filter(|&cmd| cmd.proto.ident == "GetError" || names.contains(&symbol(cmd)))
+        .cloned()
+        .collect();
+    Ok(allowed)
+}
+
+/// Generates the list of Rust `Arg`s that a `Cmd` requires.
+///
+/// Unlike `generators::gen_parameters`, parameter names which are keywords in any Rust edition
+/// (such as `box` or `gen`) are written as raw identifiers.
+pub(crate) fn gen_parameters(cmd: &Cmd, with_idents: bool, with_types: bool) -> Vec<String> {
+    cmd.params
+        .iter()
+        .map(|binding| {
+            let ident = match binding.ident.as_str() {
+                "abstract" | "async" | "await" | "become" | "box" | "do" | "dyn" | "final"
+                | "gen" | "macro" | "override" | "priv" | "try" | "typeof" | "unsized"
+                | "virtual" | "yield" => format!("r#{}", binding.ident),
+                _ => binding.ident.clone(),
+            };
+            match (with_idents, with_types) {
+                (true, true) => format!("{}: {}", ident, binding.ty),
+                (true, false) => ident,
+                (false, true) => binding.ty.to_string(),
+                (false, false) => panic!(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_generator::{Api, Binding, Cmd, Fallbacks, Profile, Registry};
+    use std::io;
+
+    use super::{gen_parameters, with_command_allowlist};
+
+    #[test]
+    fn allowlist_rejects_an_unknown_command() {
+        let registry = Registry::new(Api::Gl, (1, 0), Profile::Core, Fallbacks::None, []);
+        let names = ["glClear".to_string(), "glFoo".to_string()];
+        let error = with_command_allowlist(&registry, &names).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("there is no command `glFoo`"), "{}", error);
+    }
+
+    #[test]
+    fn allowlist_keeps_get_error() {
+        let registry = Registry::new(Api::Gl, (1, 0), Profile::Core, Fallbacks::None, []);
+        let allowed = with_command_allowlist(&registry, &["glClear".to_string()]).unwrap();
+        let idents: Vec<_> = allowed.cmds.iter().map(|cmd| cmd.proto.ident.as_str()).collect();
+        assert_eq!(idents, ["Clear", "GetError"]);
+    }
+
+    #[test]
+    fn parameters_escape_the_keywords() {
+        let binding = |ident: &str, ty: &'static str| Binding {
+            ident: ident.to_string(),
+            ty: ty.into(),
+            group: None,
+        };
+        let cmd = Cmd {
+            proto: binding("Foo", "()"),
+            params: vec![
+                binding("box", "types::GLuint"),
+                binding("gen", "types::GLenum"),
+                binding("type_", "types::GLenum"),
+            ],
+            alias: None,
+            vecequiv: None,
+            glx: None,
+        };
+        assert_eq!(
+            gen_parameters(&cmd, true, true),
+            ["r#box: types::GLuint", "r#gen: types::GLenum", "type_: types::GLenum"]
+        );
+        assert_eq!(gen_parameters(&cmd, true, false), ["r#box", "r#gen", "type_"]);
+        assert_eq!(gen_parameters(&cmd, false, true), ["types::GLuint", "types::GLenum", "types::GLenum"]);
+    }
+}